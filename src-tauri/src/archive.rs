@@ -0,0 +1,352 @@
+// Scrape session archives: every page a run visited, as the scraper last saw it, with
+// the run's progress events and results. Replaying an archive runs the same scrape
+// against a local server serving those pages, so a bad result can be reproduced
+// after the marketplace content has moved on.
+//
+//   <dir>/session.json   the request, known shop names, and visited URL -> page file
+//   <dir>/pages/*.html   DOM snapshots, taken when the scraper leaves a page
+//   <dir>/events.jsonl   shops found, queries searched, warnings, in order
+//   <dir>/results.json   the shops of the recorded run
+//
+// Replayed pages are served at `http://<original host>.localhost:<port>/<path>`, which
+// Chrome resolves to the loopback address, so links within a page keep their host.
+// Page scripts are removed before serving; the scraper's own scripts still run.
+
+use chrono::Local;
+use kuchikiki::traits::*;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+use crate::models::{ScrapeOptions, ScrapeRun, SearchQuery, ShopResults};
+use crate::progress::ProgressEvent;
+use crate::shop_names::ShopNames;
+use crate::wait::Page;
+
+const SESSION_FILE: &str = "session.json";
+const RESULTS_FILE: &str = "results.json";
+const EVENTS_FILE: &str = "events.jsonl";
+const PAGES_DIR: &str = "pages";
+
+// Longest request head the replay server reads
+const MAX_REQUEST: usize = 64 * 1024;
+
+// Remembers on the page which URL was asked for, since redirects change location.href
+const MARK: &str = "window.__satuTokoRequested = arguments[0];";
+
+// Requested URL, current URL and DOM of the page, or null for a page being left or
+// one that never loaded
+const SNAPSHOT: &str = r#"
+if (window.__satuTokoLeaving || !document.documentElement) return null;
+if (!location.href.startsWith("http")) return null;
+return [window.__satuTokoRequested || null, location.href, document.documentElement.outerHTML];
+"#;
+
+/// The recorded request and where its pages were saved.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct SessionInfo {
+    pub recorded_at: String,
+    pub platform: String,
+    pub queries: Vec<SearchQuery>,
+    pub limit: usize,
+    pub options: ScrapeOptions,
+    // Visited URL (as requested and as shown by the browser) -> file in pages/
+    pub pages: BTreeMap<String, String>,
+    // Shopee shop names the recorded run started out knowing
+    pub shop_names: ShopNames,
+}
+
+/// An archive being recorded, or one being replayed once `serve` has started.
+#[derive(Debug)]
+pub struct SessionArchive {
+    dir: PathBuf,
+    recording: bool,
+    info: Mutex<SessionInfo>,
+    // Recording only
+    events: Mutex<Option<File>>,
+    // Replay server port
+    port: OnceLock<u16>,
+}
+
+// Same page whatever the fragment
+fn page_key(url: &str) -> String {
+    url.split('#').next().unwrap_or(url).to_string()
+}
+
+impl SessionArchive {
+    /// Starts recording into `dir`, which must not hold a session yet.
+    pub fn create(
+        dir: &Path,
+        platform: &str,
+        queries: &[SearchQuery],
+        limit: usize,
+        options: &ScrapeOptions,
+    ) -> Result<Self, String> {
+        if dir.join(SESSION_FILE).exists() {
+            return Err(format!("{} already holds a recorded session", dir.display()));
+        }
+        fs::create_dir_all(dir.join(PAGES_DIR))
+            .map_err(|e| format!("Failed to create session archive: {}", e))?;
+        let events = File::create(dir.join(EVENTS_FILE))
+            .map_err(|e| format!("Failed to create session events file: {}", e))?;
+
+        let mut options = options.clone();
+        options.record_dir = None;
+        let info = SessionInfo {
+            recorded_at: Local::now().to_rfc3339(),
+            platform: platform.to_string(),
+            queries: queries.to_vec(),
+            limit,
+            options,
+            pages: BTreeMap::new(),
+            shop_names: ShopNames::default(),
+        };
+        info!("Recording session to {}", dir.display());
+        Ok(SessionArchive {
+            dir: dir.to_path_buf(),
+            recording: true,
+            info: Mutex::new(info),
+            events: Mutex::new(Some(events)),
+            port: OnceLock::new(),
+        })
+    }
+
+    /// Opens a recorded session for replay.
+    pub fn open(dir: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(dir.join(SESSION_FILE))
+            .map_err(|e| format!("Failed to read session archive: {}", e))?;
+        let info: SessionInfo = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse session archive: {}", e))?;
+        Ok(SessionArchive {
+            dir: dir.to_path_buf(),
+            recording: false,
+            info: Mutex::new(info),
+            events: Mutex::new(None),
+            port: OnceLock::new(),
+        })
+    }
+
+    pub fn info(&self) -> SessionInfo {
+        self.info.lock().unwrap().clone()
+    }
+
+    pub fn is_replay(&self) -> bool {
+        !self.recording
+    }
+
+    /// Shops of the recorded run.
+    pub fn recorded_results(&self) -> Result<Vec<ShopResults>, String> {
+        let content = fs::read_to_string(self.dir.join(RESULTS_FILE))
+            .map_err(|e| format!("Failed to read recorded results: {}", e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse recorded results: {}", e))
+    }
+
+    /// Recording: keeps the shop names the run starts with, so a replay names the
+    /// shops the same way whatever the cache has learnt since.
+    pub(crate) fn record_shop_names(&self, names: &ShopNames) {
+        self.info.lock().unwrap().shop_names = names.clone();
+    }
+
+    /// Where the browser should go for `url`: the replay server when replaying.
+    pub(crate) fn page_url(&self, url: &str) -> String {
+        let Some(port) = self.port.get() else {
+            return url.to_string();
+        };
+        let rest = url
+            .strip_prefix("https://")
+            .or_else(|| url.strip_prefix("http://"))
+            .unwrap_or(url);
+        let (host, path) = match rest.find('/') {
+            Some(index) => rest.split_at(index),
+            None => (rest, "/"),
+        };
+        format!("http://{}.localhost:{}{}", host, port, path)
+    }
+
+    /// Recording: notes on the page that was opened for `url`.
+    pub(crate) async fn arrived<P: Page>(&self, page: &P, url: &str) {
+        if self.recording {
+            page.run(MARK, vec![json!(url)]).await;
+        }
+    }
+
+    /// Recording: saves the page currently shown, before the scraper leaves it or at
+    /// the end of a run. A page seen again replaces the earlier snapshot.
+    pub(crate) async fn leave<P: Page>(&self, page: &P) {
+        if !self.recording {
+            return;
+        }
+        let Some(Value::Array(parts)) = page.run(SNAPSHOT, vec![]).await else {
+            return;
+        };
+        let [requested, current, html] = parts.as_slice() else {
+            return;
+        };
+        let (Some(current), Some(html)) = (current.as_str(), html.as_str()) else {
+            return;
+        };
+        let mut keys = vec![page_key(current)];
+        if let Some(requested) = requested.as_str() {
+            keys.push(page_key(requested));
+        }
+
+        let mut info = self.info.lock().unwrap();
+        let file = keys
+            .iter()
+            .find_map(|key| info.pages.get(key).cloned())
+            .unwrap_or_else(|| format!("{:04}.html", info.pages.len() + 1));
+        let path = self.dir.join(PAGES_DIR).join(&file);
+        if let Err(e) = fs::write(&path, format!("<!DOCTYPE html>\n{}", html)) {
+            warn!("Failed to save page snapshot of {}: {}", current, e);
+            return;
+        }
+        for key in keys {
+            info.pages.insert(key, file.clone());
+        }
+    }
+
+    /// Recording: appends one progress event.
+    pub(crate) fn event(&self, event: &ProgressEvent) {
+        let mut events = self.events.lock().unwrap();
+        let Some(file) = events.as_mut() else {
+            return;
+        };
+        match serde_json::to_string(event) {
+            Ok(line) => {
+                if let Err(e) = writeln!(file, "{}", line) {
+                    warn!("Failed to write session event: {}", e);
+                }
+            }
+            Err(e) => warn!("Failed to serialize session event: {}", e),
+        }
+    }
+
+    /// Recording: writes the page index and, for a run that finished, its results.
+    pub fn save(&self, run: Option<&ScrapeRun>) -> Result<(), String> {
+        if let Some(run) = run {
+            let json = serde_json::to_string_pretty(&run.results)
+                .map_err(|e| format!("Failed to serialize results: {}", e))?;
+            fs::write(self.dir.join(RESULTS_FILE), json)
+                .map_err(|e| format!("Failed to write recorded results: {}", e))?;
+        }
+        let info = self.info();
+        let json = serde_json::to_string_pretty(&info)
+            .map_err(|e| format!("Failed to serialize session archive: {}", e))?;
+        fs::write(self.dir.join(SESSION_FILE), json)
+            .map_err(|e| format!("Failed to write session archive: {}", e))?;
+        info!("Session recorded: {} page(s) in {}", info.pages.len(), self.dir.display());
+        Ok(())
+    }
+
+    // Recorded page for `url`, without its scripts
+    fn page(&self, url: &str) -> Option<String> {
+        let file = self.info.lock().unwrap().pages.get(&page_key(url)).cloned()?;
+        match fs::read_to_string(self.dir.join(PAGES_DIR).join(file)) {
+            Ok(html) => Some(strip_scripts(&html)),
+            Err(e) => {
+                warn!("Failed to read page snapshot of {}: {}", url, e);
+                None
+            }
+        }
+    }
+
+    /// Starts serving the recorded pages; `page_url` points at the server from now on.
+    /// The server stops when the returned handle is dropped.
+    pub async fn serve(self: &Arc<Self>) -> Result<ReplayServer, String> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|e| format!("Failed to start replay server: {}", e))?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| format!("Failed to start replay server: {}", e))?
+            .port();
+        self.port
+            .set(port)
+            .map_err(|_| "Replay server already running".to_string())?;
+
+        let archive = Arc::clone(self);
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve_request(Arc::clone(&archive), stream));
+            }
+        });
+        info!("Replaying {} on port {}", self.dir.display(), port);
+        Ok(ReplayServer { task })
+    }
+}
+
+/// Running replay server; stops when dropped.
+pub struct ReplayServer {
+    task: JoinHandle<()>,
+}
+
+impl Drop for ReplayServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+// Without the marketplace's scripts the snapshot stays as it was recorded, instead of
+// being rendered again (or redirected) by an app that can't reach its API
+fn strip_scripts(html: &str) -> String {
+    let document = kuchikiki::parse_html().one(html).document_node;
+    if let Ok(nodes) = document.select("script, base, meta[http-equiv]") {
+        for node in nodes.collect::<Vec<_>>() {
+            node.as_node().detach();
+        }
+    }
+    document.to_string()
+}
+
+async fn serve_request(archive: Arc<SessionArchive>, mut stream: TcpStream) {
+    // Only the request head is needed: the browser only sends GETs
+    let mut request = Vec::new();
+    let mut chunk = [0u8; 4096];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < MAX_REQUEST {
+        match stream.read(&mut chunk).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => request.extend_from_slice(&chunk[..n]),
+        }
+    }
+    let request = String::from_utf8_lossy(&request);
+    let mut lines = request.lines();
+    let target = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .unwrap_or("/");
+    let host = lines
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.eq_ignore_ascii_case("host"))
+        .map(|(_, value)| value.trim())
+        .unwrap_or("");
+    let host = host.split(':').next().unwrap_or(host);
+    let url = format!("https://{}{}", host.trim_end_matches(".localhost"), target);
+
+    let (status, body) = match archive.page(&url) {
+        Some(html) => ("200 OK", html),
+        None => {
+            info!("Not in the session archive: {}", url);
+            (
+                "404 Not Found",
+                format!("<html><body><p>Not recorded: {}</p></body></html>", url),
+            )
+        }
+    };
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        body.len()
+    );
+    let _ = stream.write_all(head.as_bytes()).await;
+    let _ = stream.write_all(body.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
@@ -0,0 +1,107 @@
+// Basket totals: what buying the whole list from a single shop would cost
+
+use crate::models::{BasketLine, BasketSummary, Product, ShopResults};
+use crate::parse::parse_price;
+use crate::shipping::ShippingEstimator;
+
+// Quantity actually bought: the desired quantity, raised to the product's minimum order
+pub(crate) fn effective_quantity(product: &Product, desired: u32) -> u32 {
+    let min_order = product.detail.as_ref().and_then(|d| d.min_order).unwrap_or(1);
+    desired.max(min_order).max(1)
+}
+
+// False only when the detail page showed less stock than we need
+fn can_fill(product: &Product, quantity: u32) -> bool {
+    match product.detail.as_ref().and_then(|d| d.stock) {
+        Some(stock) => stock >= u64::from(quantity),
+        None => true,
+    }
+}
+
+// Index of the product picked for each query result: the cheapest line total among
+// products that can fill the quantity, or the cheapest overall when none can.
+pub fn cheapest_indices(shop: &ShopResults) -> Vec<Option<usize>> {
+    shop.results
+        .iter()
+        .map(|result| {
+            let priced: Vec<(usize, u64, bool)> = result
+                .products
+                .iter()
+                .enumerate()
+                .filter_map(|(index, product)| {
+                    let price = parse_price(&product.price)?;
+                    let quantity = effective_quantity(product, result.quantity);
+                    Some((index, price.saturating_mul(u64::from(quantity)), can_fill(product, quantity)))
+                })
+                .collect();
+
+            priced
+                .iter()
+                .filter(|(_, _, fillable)| *fillable)
+                .min_by_key(|(_, total, _)| *total)
+                .or_else(|| priced.iter().min_by_key(|(_, total, _)| *total))
+                .map(|(index, _, _)| *index)
+        })
+        .collect()
+}
+
+pub fn summarize(shop: &ShopResults, estimator: Option<&ShippingEstimator>) -> BasketSummary {
+    let mut summary = BasketSummary::default();
+    let mut chosen: Vec<(&Product, u32)> = Vec::new();
+
+    for (result, index) in shop.results.iter().zip(cheapest_indices(shop)) {
+        let Some(product) = index.map(|i| &result.products[i]) else {
+            summary.missing_queries.push(result.query.clone());
+            continue;
+        };
+
+        let unit_price = parse_price(&product.price).unwrap_or(0);
+        let quantity = effective_quantity(product, result.quantity);
+        let warning = match product.detail.as_ref().and_then(|d| d.stock) {
+            Some(stock) if stock < u64::from(quantity) => {
+                Some(format!("stok {} < {}", stock, quantity))
+            }
+            _ if quantity > result.quantity => Some(format!("min. order {}", quantity)),
+            _ => None,
+        };
+
+        let line_total = unit_price.saturating_mul(u64::from(quantity));
+        summary.items_total = summary.items_total.saturating_add(line_total);
+        summary.lines.push(BasketLine {
+            query: result.query.clone(),
+            product_name: product.name.clone(),
+            product_link: product.link.clone(),
+            unit_price,
+            quantity,
+            line_total,
+            warning,
+        });
+        chosen.push((product, quantity));
+    }
+
+    if let Some(estimator) = estimator {
+        if !chosen.is_empty() {
+            let default_weight = estimator.config().default_weight_grams;
+            // Imported lists can ask for thousands of heavy items: sum in u64
+            let weight_grams = chosen
+                .iter()
+                .map(|(p, quantity)| {
+                    let unit = p.detail.as_ref().and_then(|d| d.weight_grams).unwrap_or(default_weight);
+                    u64::from(unit) * u64::from(*quantity)
+                })
+                .fold(0u64, u64::saturating_add);
+            let origin = shop
+                .profile
+                .as_ref()
+                .and_then(|p| p.city.clone())
+                .or_else(|| chosen.iter().map(|(p, _)| p.location.clone()).find(|l| !l.is_empty()))
+                .unwrap_or_default();
+            summary.shipping = estimator.estimate(&origin, weight_grams);
+        }
+    }
+
+    summary.total = summary
+        .items_total
+        .saturating_add(summary.shipping.as_ref().map_or(0, |s| s.cost));
+    summary
+}
@@ -0,0 +1,442 @@
+// Command-line entry point for scripted scraping (cron jobs, shell scripts).
+// Uses the same scraper, chromedriver handling and exporters as the app, without the webview.
+//
+//   satu-toko-cli search --platform tokopedia --limit 20 -q "kabel nym" -q "10x fitting lampu" --out hasil.xlsx
+//   satu-toko-cli search -q "kabel nym" -q "fitting lampu" --http tokopedia
+//   satu-toko-cli search -q "kabel nym" -q "fitting lampu" --refresh
+//   satu-toko-cli search -q "kabel nym" -q "saklar broco" --seed rarest
+//   satu-toko-cli search -q "kabel nym" --out hasil.json && satu-toko-cli extend hasil.json -q "stop kontak"
+//   satu-toko-cli driver status|install|redownload
+//   satu-toko-cli diagnose --platform shopee
+//   satu-toko-cli search -q "kabel nym" --record sesi-kabel && satu-toko-cli replay sesi-kabel
+//
+// Results go to stdout (JSON) or to --out; progress and logs go to stderr.
+
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use satu_toko_lib::models::{
+    FetchMode, PlatformStatus, ScrapeOptions, ScrapeRun, SearchQuery, SeedMode, ShopResults,
+};
+use satu_toko_lib::progress::ProgressEvent;
+use satu_toko_lib::{chromedriver, coordinator, diagnose, export, scraper};
+
+#[derive(Parser)]
+#[command(name = "satu-toko-cli", version, about = "Cari toko yang menjual semua barang di daftar belanja")]
+struct Cli {
+    /// Show scraper logs on stderr
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Search shops that sell every query
+    Search(SearchArgs),
+    /// Manage the patched chromedriver
+    Driver {
+        #[command(subcommand)]
+        action: DriverAction,
+    },
+    /// Check that every selector still matches on a canned search
+    Diagnose {
+        #[arg(long, default_value = "tokopedia", value_parser = ["tokopedia", "shopee"])]
+        platform: String,
+
+        /// Where page source and screenshot go when a check fails
+        #[arg(long, default_value = ".")]
+        bundle_dir: PathBuf,
+    },
+    /// Search more queries in the shops of an earlier search's JSON results
+    Extend(ExtendArgs),
+    /// Run a search recorded with --record again against its saved pages
+    Replay {
+        /// Folder given to --record
+        dir: PathBuf,
+
+        /// Output file (.json, .csv or .xlsx) for the replayed results
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+}
+
+#[derive(Args)]
+struct SearchArgs {
+    #[arg(long, default_value = "tokopedia", value_parser = ["tokopedia", "shopee", "all"])]
+    platform: String,
+
+    /// Number of products from each seed query used to discover shops
+    #[arg(long, default_value_t = 20)]
+    limit: usize,
+
+    /// Query, repeatable; "10x kabel NYM" sets a quantity
+    #[arg(short, long = "query", required = true)]
+    queries: Vec<String>,
+
+    /// Queries whose results the shops are discovered from: "first", "rarest" (the
+    /// one with the fewest results), a query number, or numbers like "1,3" to combine
+    #[arg(long, default_value = "first", value_parser = parse_seed)]
+    seed: SeedMode,
+
+    /// Visit product pages for stock, variants and weight
+    #[arg(long)]
+    deep: bool,
+
+    /// Browser sessions running at once with --platform all
+    #[arg(long, default_value_t = 2)]
+    sessions: usize,
+
+    /// Shops searched at once per browser session (Tokopedia)
+    #[arg(long, default_value_t = 3)]
+    tabs: usize,
+
+    /// Minimum milliseconds between page loads in one session
+    #[arg(long, default_value_t = 500)]
+    delay_ms: u64,
+
+    /// Longest wait in seconds for a page or element to show up
+    #[arg(long, default_value_t = 10)]
+    wait_timeout: u64,
+
+    /// Extra attempts for a page that fails to load
+    #[arg(long, default_value_t = 2)]
+    retries: u32,
+
+    /// Output file (.json, .csv or .xlsx); JSON on stdout when omitted
+    #[arg(long)]
+    out: Option<PathBuf>,
+
+    /// Save every visited page and the results into this folder, for `replay`
+    #[arg(long)]
+    record: Option<PathBuf>,
+
+    /// Download this platform's search and shop pages without a browser, repeatable;
+    /// the browser still takes over when the site blocks the download
+    #[arg(long = "http", value_parser = ["tokopedia"])]
+    http: Vec<String>,
+
+    /// Seconds a shop's results for a query are reused by later searches; 0 turns
+    /// the result cache off
+    #[arg(long, default_value_t = 6 * 60 * 60)]
+    cache_ttl: u64,
+
+    /// Search every shop again instead of using cached results
+    #[arg(long)]
+    refresh: bool,
+}
+
+#[derive(Args)]
+struct ExtendArgs {
+    /// JSON results of an earlier search (stdout or --out of `search`)
+    results: PathBuf,
+
+    /// New query, repeatable; queries the results already have are skipped
+    #[arg(short, long = "query", required = true)]
+    queries: Vec<String>,
+
+    /// Products read per shop search
+    #[arg(long, default_value_t = 20)]
+    limit: usize,
+
+    /// Visit the new products' pages for stock, variants and weight
+    #[arg(long)]
+    deep: bool,
+
+    /// Shops searched at once per browser session (Tokopedia)
+    #[arg(long, default_value_t = 3)]
+    tabs: usize,
+
+    /// Search every shop again instead of using cached results
+    #[arg(long)]
+    refresh: bool,
+
+    /// Output file (.json, .csv or .xlsx) for all shops; JSON on stdout when omitted
+    #[arg(long)]
+    out: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum DriverAction {
+    /// Show Chrome and chromedriver versions
+    Status,
+    /// Download chromedriver if missing or incompatible
+    Install,
+    /// Remove and download chromedriver again
+    Redownload,
+}
+
+// `--seed`: query numbers are 1-based, as the user counts them
+fn parse_seed(value: &str) -> Result<SeedMode, String> {
+    match value {
+        "first" => return Ok(SeedMode::First),
+        "rarest" => return Ok(SeedMode::Rarest),
+        _ => {}
+    }
+    let mut indices = Vec::new();
+    for number in value.split(',') {
+        match number.trim().parse::<usize>() {
+            Ok(n) if n > 0 => indices.push(n - 1),
+            _ => return Err(format!("expected first, rarest or query numbers, got {}", value)),
+        }
+    }
+    Ok(match indices[..] {
+        [index] => SeedMode::Query { index },
+        _ => SeedMode::Union { indices },
+    })
+}
+
+fn print_progress(event: ProgressEvent) {
+    match event {
+        ProgressEvent::ShopStarted {
+            platform,
+            shop,
+            index,
+            total,
+        } => eprintln!("[{}] Toko {}/{}: {}", platform, index, total, shop),
+        ProgressEvent::ShopFinished { shop } => {
+            let products: usize = shop.results.iter().map(|r| r.products.len()).sum();
+            let cached = shop.results.iter().filter(|r| r.cached_age_secs.is_some()).count();
+            if cached > 0 {
+                eprintln!(
+                    "[{}] {} - {} produk ({} kueri dari cache)",
+                    shop.platform, shop.shop_name, products, cached
+                );
+            } else {
+                eprintln!("[{}] {} - {} produk", shop.platform, shop.shop_name, products);
+            }
+        }
+        ProgressEvent::Warning { platform, message } => {
+            eprintln!("[{}] Peringatan: {}", platform, message)
+        }
+        _ => {}
+    }
+}
+
+async fn search(args: SearchArgs) -> Result<(), String> {
+    let SearchArgs {
+        platform,
+        limit,
+        queries,
+        seed,
+        deep,
+        sessions,
+        tabs,
+        delay_ms,
+        wait_timeout,
+        retries,
+        out,
+        record,
+        http,
+        cache_ttl,
+        refresh,
+    } = args;
+
+    // Fail on a bad --out before spending minutes scraping
+    if let Some(path) = &out {
+        export::output_format(path)?;
+    }
+
+    let queries: Vec<SearchQuery> = queries.iter().map(|q| SearchQuery::from(q.as_str())).collect();
+    let options = ScrapeOptions {
+        deep_scrape: deep,
+        max_sessions: sessions,
+        max_tabs: tabs,
+        politeness_ms: delay_ms,
+        wait_timeout_secs: wait_timeout,
+        max_retries: retries,
+        record_dir: record.map(|dir| dir.to_string_lossy().to_string()),
+        fetch_modes: http.into_iter().map(|platform| (platform, FetchMode::Http)).collect(),
+        cache_ttl_secs: cache_ttl,
+        refresh_cache: refresh,
+        seed,
+        ..Default::default()
+    };
+
+    let run = coordinator::scrape_products(queries, platform, limit, options, &print_progress).await?;
+    print_reports(&run)?;
+    write_results(&run.results, out)
+}
+
+// Platform outcomes on stderr; Err when no platform produced anything
+fn print_reports(run: &ScrapeRun) -> Result<(), String> {
+    for report in &run.platforms {
+        match &report.status {
+            PlatformStatus::Ok => eprintln!("[{}] OK, {} toko", report.platform, report.shops),
+            PlatformStatus::Partial { reason } => eprintln!(
+                "[{}] Sebagian, {} toko: {}",
+                report.platform, report.shops, reason
+            ),
+            PlatformStatus::Failed { reason } => {
+                eprintln!("[{}] Gagal: {}", report.platform, reason)
+            }
+        }
+    }
+    // No platform at all (e.g. extending shops of no supported platform) isn't a failure
+    if !run.platforms.is_empty()
+        && run
+            .platforms
+            .iter()
+            .all(|r| matches!(r.status, PlatformStatus::Failed { .. }))
+    {
+        return Err("All platforms failed".to_string());
+    }
+    eprintln!("Selesai: {} toko", run.results.len());
+    Ok(())
+}
+
+fn write_results(results: &[ShopResults], out: Option<PathBuf>) -> Result<(), String> {
+    match out {
+        Some(path) => {
+            export::write_results(results, &path)?;
+            eprintln!("Hasil disimpan ke {}", path.display());
+        }
+        None => {
+            let json = serde_json::to_string_pretty(results)
+                .map_err(|e| format!("Failed to serialize results: {}", e))?;
+            println!("{}", json);
+        }
+    }
+    Ok(())
+}
+
+async fn extend(args: ExtendArgs) -> Result<(), String> {
+    let ExtendArgs {
+        results,
+        queries,
+        limit,
+        deep,
+        tabs,
+        refresh,
+        out,
+    } = args;
+    if let Some(path) = &out {
+        export::output_format(path)?;
+    }
+
+    let content = std::fs::read_to_string(&results)
+        .map_err(|e| format!("Failed to read {}: {}", results.display(), e))?;
+    let shops: Vec<ShopResults> = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse {}: {}", results.display(), e))?;
+    let queries: Vec<SearchQuery> = queries.iter().map(|q| SearchQuery::from(q.as_str())).collect();
+    let options = ScrapeOptions {
+        deep_scrape: deep,
+        max_tabs: tabs,
+        refresh_cache: refresh,
+        ..Default::default()
+    };
+
+    let run = coordinator::extend_scrape(shops, queries, limit, options, &print_progress).await?;
+    print_reports(&run)?;
+    write_results(&run.results, out)
+}
+
+async fn replay(dir: PathBuf, out: Option<PathBuf>) -> Result<(), String> {
+    if let Some(path) = &out {
+        export::output_format(path)?;
+    }
+    let outcome = coordinator::replay_session(&dir, &print_progress).await?;
+    print_reports(&outcome.run)?;
+    write_results(&outcome.run.results, out)?;
+
+    if outcome.differences.is_empty() {
+        eprintln!("Hasil sama dengan rekaman");
+        return Ok(());
+    }
+    for shop in &outcome.differences {
+        eprintln!("  Berbeda: {}", shop);
+    }
+    Err(format!("{} shop(s) differ from the recording", outcome.differences.len()))
+}
+
+async fn driver(action: DriverAction) -> Result<(), String> {
+    match action {
+        DriverAction::Status => {
+            let (chrome, driver) = scraper::get_chrome_and_driver_info().await?;
+            println!("Chrome: {}", chrome);
+            println!("Chromedriver: {}", driver);
+        }
+        DriverAction::Install => {
+            println!("{}", chromedriver::ensure_chromedriver().await?);
+        }
+        DriverAction::Redownload => {
+            println!("{}", chromedriver::redownload_chromedriver().await?);
+        }
+    }
+    Ok(())
+}
+
+async fn diagnose(platform: String, bundle_dir: PathBuf) -> Result<(), String> {
+    let report = diagnose::diagnose_platform(&platform, &bundle_dir).await?;
+    println!(
+        "{} (selectors v{}{}), query \"{}\", {} card(s)",
+        report.platform,
+        report.selectors_version,
+        report
+            .selectors_override
+            .as_ref()
+            .map(|p| format!(", override {}", p))
+            .unwrap_or_default(),
+        report.query,
+        report.cards_sampled
+    );
+    for error in &report.errors {
+        println!("  Gagal dimuat: {}", error);
+    }
+    for check in &report.checks {
+        let mark = match (check.count > 0, check.required) {
+            (true, _) => "OK  ",
+            (false, true) => "GAGAL",
+            (false, false) => "-   ",
+        };
+        let rule = check
+            .matched_rule
+            .map(|i| format!(" (aturan {})", i + 1))
+            .unwrap_or_default();
+        println!("  {} {:<16} {:<7} {}{}", mark, check.key, check.page, check.count, rule);
+    }
+    if let Some(bundle) = &report.bundle {
+        println!("Sumber halaman dan screenshot: {}", bundle);
+    }
+    if !report.healthy {
+        return Err("Selector check failed".to_string());
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    // Logs always go to stderr so stdout stays clean for JSON output
+    env_logger::Builder::new()
+        .filter_level(if cli.verbose {
+            log::LevelFilter::Info
+        } else {
+            log::LevelFilter::Warn
+        })
+        .target(env_logger::Target::Stderr)
+        .init();
+
+    let result = match cli.command {
+        Command::Search(args) => search(args).await,
+        Command::Driver { action } => driver(action).await,
+        Command::Diagnose {
+            platform,
+            bundle_dir,
+        } => diagnose(platform, bundle_dir).await,
+        Command::Extend(args) => extend(args).await,
+        Command::Replay { dir, out } => replay(dir, out).await,
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
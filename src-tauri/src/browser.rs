@@ -0,0 +1,131 @@
+// Browser abstraction: what the platform scrapers do with a browser, so their flows
+// run against thirtyfour in the app and against the in-memory DOM of `fake_browser`
+// in tests. Navigation, scripts and the wait primitives come from `wait::Page`.
+
+use std::path::Path;
+use thirtyfour::prelude::*;
+
+use crate::wait::Page;
+
+/// How an element is looked up.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Locator<'a> {
+    Css(&'a str),
+    XPath(&'a str),
+}
+
+impl Locator<'_> {
+    fn by(self) -> By {
+        match self {
+            Locator::Css(css) => By::Css(css),
+            Locator::XPath(xpath) => By::XPath(xpath),
+        }
+    }
+}
+
+/// Where elements are looked up: the whole page, or inside one element.
+pub(crate) trait Scope {
+    type Element: Element;
+
+    async fn find(&self, locator: Locator<'_>) -> Option<Self::Element>;
+    async fn find_all(&self, locator: Locator<'_>) -> Vec<Self::Element>;
+}
+
+/// An element on the page. Reads that fail come back empty: the scrapers treat a
+/// value that can't be read like one that isn't there.
+pub(crate) trait Element: Scope<Element = Self> + Sized {
+    async fn attr(&self, name: &str) -> Option<String>;
+    async fn text(&self) -> String;
+    async fn outer_html(&self) -> String;
+    async fn is_displayed(&self) -> bool;
+    async fn is_enabled(&self) -> bool;
+    async fn click(&self) -> Result<(), String>;
+    async fn clear(&self);
+    // "\n" submits, like pressing Enter
+    async fn send_keys(&self, text: &str) -> Result<(), String>;
+}
+
+/// The page a scraper works on.
+pub(crate) trait Browser: Page + Scope {
+    async fn current_url(&self) -> String;
+    async fn screenshot(&self, path: &Path) -> Result<(), String>;
+}
+
+impl Scope for WebDriver {
+    type Element = WebElement;
+
+    async fn find(&self, locator: Locator<'_>) -> Option<WebElement> {
+        self.handle.find(locator.by()).await.ok()
+    }
+
+    async fn find_all(&self, locator: Locator<'_>) -> Vec<WebElement> {
+        self.handle.find_all(locator.by()).await.unwrap_or_default()
+    }
+}
+
+impl Browser for WebDriver {
+    async fn current_url(&self) -> String {
+        self.handle
+            .current_url()
+            .await
+            .map(|u| u.to_string())
+            .unwrap_or_default()
+    }
+
+    async fn screenshot(&self, path: &Path) -> Result<(), String> {
+        self.handle
+            .screenshot(path)
+            .await
+            .map_err(|e| format!("Failed to take screenshot: {}", e))
+    }
+}
+
+impl Scope for WebElement {
+    type Element = WebElement;
+
+    async fn find(&self, locator: Locator<'_>) -> Option<WebElement> {
+        WebElement::find(self, locator.by()).await.ok()
+    }
+
+    async fn find_all(&self, locator: Locator<'_>) -> Vec<WebElement> {
+        WebElement::find_all(self, locator.by()).await.unwrap_or_default()
+    }
+}
+
+impl Element for WebElement {
+    async fn attr(&self, name: &str) -> Option<String> {
+        WebElement::attr(self, name).await.ok().flatten()
+    }
+
+    async fn text(&self) -> String {
+        WebElement::text(self).await.unwrap_or_default()
+    }
+
+    async fn outer_html(&self) -> String {
+        WebElement::outer_html(self).await.unwrap_or_default()
+    }
+
+    async fn is_displayed(&self) -> bool {
+        WebElement::is_displayed(self).await.unwrap_or(false)
+    }
+
+    async fn is_enabled(&self) -> bool {
+        WebElement::is_enabled(self).await.unwrap_or(false)
+    }
+
+    async fn click(&self) -> Result<(), String> {
+        WebElement::click(self)
+            .await
+            .map_err(|e| format!("Failed to click: {}", e))
+    }
+
+    async fn clear(&self) {
+        let _ = WebElement::clear(self).await;
+    }
+
+    async fn send_keys(&self, text: &str) -> Result<(), String> {
+        WebElement::send_keys(self, text)
+            .await
+            .map_err(|e| format!("Failed to type: {}", e))
+    }
+}
@@ -1,15 +1,12 @@
-use log::{error, info};
+use log::info;
 use rand::Rng;
 use std::env;
 use std::fs;
 use std::io::Cursor;
 use std::path::{Path, PathBuf};
-use std::process::Command;
 use zip::ZipArchive;
 
 pub async fn ensure_chromedriver() -> Result<String, String> {
-    use reqwest::Client;
-
     // Determine local app data path (multiplatform)
     let driver_dir = dirs::data_local_dir()
         .ok_or("Could not determine local data directory")?
@@ -100,7 +97,7 @@ async fn download_chromedriver(
         "https://googlechromelabs.github.io/chrome-for-testing/LATEST_RELEASE_{}",
         major_version
     );
-    println!("Version URL : {}", version_url);
+    info!("Version URL : {}", version_url);
     let response = client
         .get(&version_url)
         .send()
@@ -129,7 +126,7 @@ async fn download_chromedriver(
         driver_version, platform, platform
     );
 
-    println!("Download URL : {}", download_url);
+    info!("Download URL : {}", download_url);
 
     // Download and extract
     let response = client
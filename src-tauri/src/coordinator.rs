@@ -0,0 +1,635 @@
+// Run coordinator: one scrape run across one or more platforms, in parallel sessions
+
+use futures::stream::{self, StreamExt};
+use log::{info, warn};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use thirtyfour::WebDriver;
+
+use crate::archive::SessionArchive;
+use crate::basket;
+use crate::filter;
+use crate::http_fetch::HttpFetcher;
+use crate::models::{
+    FetchMode, PlatformReport, PlatformStatus, ScrapeOptions, ScrapeRun, SearchQuery,
+    ShopResults,
+};
+use crate::platforms::{ShopeeScraper, TokopediaScraper};
+use crate::progress::{warning, OverallStatus, ProgressEvent, ProgressSink, ScrapeStatus};
+use crate::result_cache::ResultCache;
+use crate::scraper;
+use crate::selectors::Selectors;
+use crate::session::BrowserSession;
+use crate::shipping::{self, ShippingEstimator};
+use crate::shop_names::ShopNames;
+use crate::wait::Waits;
+
+// Passes events through while keeping the shops a platform has finished, so a
+// failure halfway through doesn't throw them away.
+struct CollectingSink<'a> {
+    inner: &'a dyn ProgressSink,
+    shops: Mutex<Vec<ShopResults>>,
+}
+
+impl ProgressSink for CollectingSink<'_> {
+    fn emit(&self, event: ProgressEvent) {
+        if let ProgressEvent::ShopFinished { shop } = &event {
+            self.shops.lock().unwrap().push(shop.as_ref().clone());
+        }
+        self.inner.emit(event);
+    }
+}
+
+fn platforms_for(platform: &str) -> Result<Vec<&'static str>, String> {
+    match platform {
+        "tokopedia" => Ok(vec!["tokopedia"]),
+        "shopee" => Ok(vec!["shopee"]),
+        "all" => Ok(vec!["tokopedia", "shopee"]),
+        _ => Err("Unsupported platform".to_string()),
+    }
+}
+
+async fn scrape_platform(
+    driver: &WebDriver,
+    platform: &str,
+    queries: &[SearchQuery],
+    limit: usize,
+    options: &ScrapeOptions,
+    sel: &Selectors,
+    progress: &dyn ProgressSink,
+) -> (Vec<ShopResults>, PlatformStatus) {
+    let sink = CollectingSink {
+        inner: progress,
+        shops: Mutex::new(Vec::new()),
+    };
+    let result = match platform {
+        "tokopedia" => TokopediaScraper::scrape(driver, queries, &sink, limit, options, sel).await,
+        _ => {
+            // A replay starts from the names the recorded run knew, and teaches the cache nothing
+            let mut names = match &options.archive {
+                Some(archive) if archive.is_replay() => archive.info().shop_names,
+                Some(archive) => {
+                    let names = ShopNames::load();
+                    archive.record_shop_names(&names);
+                    names
+                }
+                None => ShopNames::load(),
+            };
+            let result = ShopeeScraper::scrape(driver, queries, &sink, limit, options, sel, &mut names).await;
+            if let Err(e) = names.save() {
+                warn!("{}", e);
+            }
+            result
+        }
+    };
+
+    match result {
+        Ok(shops) => (shops, PlatformStatus::Ok),
+        Err(reason) => {
+            warn!("{} scrape failed: {}", platform, reason);
+            let shops = sink.shops.into_inner().unwrap();
+            let status = if shops.is_empty() {
+                PlatformStatus::Failed { reason }
+            } else {
+                PlatformStatus::Partial { reason }
+            };
+            (shops, status)
+        }
+    }
+}
+
+// Keeps the latest status of every platform and adds an `Overall` event after each
+// one, so parallel platforms show up as one run.
+struct AggregatingSink<'a> {
+    inner: &'a dyn ProgressSink,
+    started: Instant,
+    // platform -> (latest status, finished)
+    platforms: Mutex<BTreeMap<String, (Option<ScrapeStatus>, bool)>>,
+}
+
+impl<'a> AggregatingSink<'a> {
+    fn new(inner: &'a dyn ProgressSink, platforms: &[&str]) -> Self {
+        AggregatingSink {
+            inner,
+            started: Instant::now(),
+            platforms: Mutex::new(
+                platforms
+                    .iter()
+                    .map(|p| (p.to_string(), (None, false)))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn overall(&self) -> OverallStatus {
+        let platforms = self.platforms.lock().unwrap();
+        let statuses = platforms.values().filter_map(|(status, _)| status.as_ref());
+        OverallStatus {
+            running: platforms
+                .iter()
+                .filter(|(_, (_, finished))| !finished)
+                .map(|(platform, _)| platform.clone())
+                .collect(),
+            shops_done: statuses.clone().map(|s| s.shops_done).sum(),
+            products_found: statuses.map(|s| s.products_found).sum(),
+            elapsed_secs: self.started.elapsed().as_secs(),
+            eta_secs: platforms
+                .values()
+                .filter(|(_, finished)| !finished)
+                .filter_map(|(status, _)| status.as_ref().and_then(|s| s.eta_secs))
+                .max(),
+        }
+    }
+}
+
+impl ProgressSink for AggregatingSink<'_> {
+    fn emit(&self, event: ProgressEvent) {
+        let changed = match &event {
+            ProgressEvent::Status(status) => {
+                let mut platforms = self.platforms.lock().unwrap();
+                let entry = platforms.entry(status.platform.clone()).or_default();
+                entry.0 = Some(status.clone());
+                true
+            }
+            ProgressEvent::PlatformFinished { report } => {
+                let mut platforms = self.platforms.lock().unwrap();
+                platforms.entry(report.platform.clone()).or_default().1 = true;
+                true
+            }
+            _ => false,
+        };
+        self.inner.emit(event);
+        if changed {
+            self.inner.emit(ProgressEvent::Overall(self.overall()));
+        }
+    }
+}
+
+// Tokopedia's search and shop pages over HTTP when the options ask for it. `None`
+// when the browser has to do the search: HTTP mode is off, a session is recorded or
+// replayed (only browser pages are archived), or Tokopedia refused the download.
+async fn fetch_platform(
+    platform: &str,
+    queries: &[SearchQuery],
+    limit: usize,
+    options: &ScrapeOptions,
+    sel: &Selectors,
+    progress: &dyn ProgressSink,
+) -> Option<(Vec<ShopResults>, PlatformStatus)> {
+    if options.fetch_mode(platform) != FetchMode::Http {
+        return None;
+    }
+    if platform != "tokopedia" || options.archive.is_some() {
+        info!("HTTP mode is not available here, using the browser for {}", platform);
+        return None;
+    }
+    let fetcher = match HttpFetcher::new(options) {
+        Ok(fetcher) => fetcher,
+        Err(e) => {
+            warn!("{}", e);
+            return None;
+        }
+    };
+    match TokopediaScraper::scrape_http(&fetcher, queries, progress, limit, options, sel).await {
+        Ok(shops) => Some((shops, PlatformStatus::Ok)),
+        Err(e) => {
+            warning(
+                platform,
+                progress,
+                format!("HTTP search failed ({}), using the browser", e),
+            );
+            None
+        }
+    }
+}
+
+// One platform in its own browser session, including the detail page visits its
+// shops need. Nothing here is an error: a session that can't be started or a failing
+// scrape becomes the platform's status, so the other platforms go on. In HTTP mode
+// the browser is only started for product pages, or when the download was refused.
+async fn run_platform(
+    platform: &str,
+    profile_path: Option<String>,
+    queries: &[SearchQuery],
+    limit: usize,
+    options: &ScrapeOptions,
+    estimator: Option<&ShippingEstimator>,
+    progress: &dyn ProgressSink,
+) -> (Vec<ShopResults>, PlatformReport) {
+    // Read per run, so an edited override applies without restarting the app
+    let sel = Selectors::load(platform);
+    let waits = Waits::from_options(options);
+    let fetch_weights = estimator.is_some_and(|e| e.config().fetch_weights);
+
+    let mut session = None;
+    let fetched = fetch_platform(platform, queries, limit, options, &sel, progress).await;
+    let over_http = fetched.is_some();
+    let (mut shops, mut status) = match fetched {
+        Some(fetched) => fetched,
+        None => match BrowserSession::start(profile_path.clone()).await {
+            Ok(started) => {
+                let started = session.insert(started);
+                scrape_platform(&started.driver, platform, queries, limit, options, &sel, progress).await
+            }
+            Err(reason) => {
+                warn!("{} browser failed to start: {}", platform, reason);
+                (Vec::new(), PlatformStatus::Failed { reason })
+            }
+        },
+    };
+    if over_http && (options.deep_scrape || fetch_weights) {
+        match BrowserSession::start(profile_path).await {
+            Ok(started) => session = Some(started),
+            // The downloaded shops are kept, without what their product pages show
+            Err(reason) => {
+                warning(platform, progress, format!("Product pages not visited: {}", reason));
+                if status == PlatformStatus::Ok {
+                    status = PlatformStatus::Partial { reason };
+                }
+            }
+        }
+    }
+
+    if let Some(session) = session {
+        let driver = &session.driver;
+        if options.deep_scrape {
+            scraper::deep_scrape_products(driver, &mut shops, queries, &sel, &waits).await;
+        }
+        if fetch_weights {
+            scraper::fill_basket_weights(driver, &mut shops, &sel, &waits).await;
+        }
+        if let Some(archive) = &waits.archive {
+            archive.leave(driver).await;
+        }
+        session.close().await;
+    }
+
+    let report = PlatformReport {
+        platform: platform.to_string(),
+        status,
+        shops: shops.len(),
+    };
+    info!("{} finished: {:?}", platform, report.status);
+    progress.emit(ProgressEvent::PlatformFinished {
+        report: report.clone(),
+    });
+    (shops, report)
+}
+
+// Chrome locks its profile directory, so when sessions overlap only one of them
+// (Shopee, which needs the login) gets the configured profile.
+fn session_profiles(platforms: &[&str], max_sessions: usize, profile_path: String) -> Vec<Option<String>> {
+    let profile_owner = if platforms.contains(&"shopee") {
+        "shopee"
+    } else {
+        platforms[0]
+    };
+    platforms
+        .iter()
+        .map(|platform| {
+            if max_sessions == 1 || *platform == profile_owner {
+                Some(profile_path.clone())
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+async fn run(
+    queries: Vec<SearchQuery>,
+    platform: String,
+    limit: usize,
+    options: ScrapeOptions,
+    progress: &dyn ProgressSink,
+) -> Result<ScrapeRun, String> {
+    let platforms = platforms_for(&platform)?;
+    let max_sessions = options.max_sessions.max(1);
+
+    // Fail on a broken rate table before starting any browser
+    let estimator = ShippingEstimator::from_config(shipping::load_config())?;
+
+    // Replayed pages don't need a login
+    let profile_path = match &options.archive {
+        Some(archive) if archive.is_replay() => String::new(),
+        _ => scraper::get_chrome_profile_path(),
+    };
+    let profiles = session_profiles(&platforms, max_sessions, profile_path);
+
+    let progress = AggregatingSink::new(progress, &platforms);
+    let progress = &progress;
+    let estimator_ref = estimator.as_ref();
+    let queries = &queries;
+    let options = &options;
+
+    let sessions: Vec<_> = platforms
+        .iter()
+        .zip(profiles)
+        .map(|(platform, profile)| {
+            run_platform(platform, profile, queries, limit, options, estimator_ref, progress)
+        })
+        .collect();
+
+    let outcomes: Vec<(Vec<ShopResults>, PlatformReport)> =
+        stream::iter(sessions).buffered(max_sessions).collect().await;
+
+    let mut run = ScrapeRun {
+        results: Vec::new(),
+        platforms: Vec::new(),
+    };
+    for (shops, report) in outcomes {
+        run.results.extend(shops);
+        run.platforms.push(report);
+    }
+
+    // Shop profiles are known now, so the minimum seller ratings can be checked
+    filter::drop_low_rated_shops(&mut run.results);
+
+    // Basket totals, with ongkir when a destination is configured
+    for shop in run.results.iter_mut() {
+        shop.basket = Some(basket::summarize(shop, estimator_ref));
+    }
+
+    Ok(run)
+}
+
+// Writes the run's decisions (shops found, queries searched, warnings) to the
+// session archive; status updates only depend on timing
+struct RecordingSink<'a> {
+    inner: &'a dyn ProgressSink,
+    archive: &'a SessionArchive,
+}
+
+impl ProgressSink for RecordingSink<'_> {
+    fn emit(&self, event: ProgressEvent) {
+        if !matches!(event, ProgressEvent::Status(_) | ProgressEvent::Overall(_)) {
+            self.archive.event(&event);
+        }
+        self.inner.emit(event);
+    }
+}
+
+// A run that also saves every page it visits into `dir`
+async fn record(
+    queries: Vec<SearchQuery>,
+    platform: String,
+    limit: usize,
+    mut options: ScrapeOptions,
+    dir: &Path,
+    progress: &dyn ProgressSink,
+) -> Result<ScrapeRun, String> {
+    let archive = Arc::new(SessionArchive::create(dir, &platform, &queries, limit, &options)?);
+    options.archive = Some(Arc::clone(&archive));
+    let progress = RecordingSink {
+        inner: progress,
+        archive: &archive,
+    };
+
+    let result = run(queries, platform, limit, options, &progress).await;
+    // The pages of a failed run are still worth keeping
+    if let Err(e) = archive.save(result.as_ref().ok()) {
+        warn!("{}", e);
+    }
+    result
+}
+
+// A run that reuses the shop results of earlier runs and keeps its own for later ones.
+// Recordings don't use the cache, so that every searched page ends up in the archive.
+async fn run_cached(
+    queries: Vec<SearchQuery>,
+    platform: String,
+    limit: usize,
+    mut options: ScrapeOptions,
+    progress: &dyn ProgressSink,
+) -> Result<ScrapeRun, String> {
+    let cache = Arc::new(ResultCache::load(options.cache_ttl_secs, options.refresh_cache));
+    options.cache = Some(Arc::clone(&cache));
+
+    let result = run(queries, platform, limit, options, progress).await;
+    // Shops searched before a failure are still worth keeping
+    if let Err(e) = cache.save() {
+        warn!("{}", e);
+    }
+    result
+}
+
+/// Runs a full scrape: each requested platform in its own browser session, up to
+/// `options.max_sessions` at once. A failing platform doesn't stop the others, and
+/// every session is torn down when the run ends or fails. Reports to `progress` as it
+/// goes and sends `ProgressEvent::Done` exactly once, also when the run fails. With
+/// `options.record_dir` every visited page is saved for `replay_session`; otherwise
+/// shop searches younger than `options.cache_ttl_secs` are taken from the result cache.
+pub async fn scrape_products(
+    queries: Vec<SearchQuery>,
+    platform: String,
+    limit: usize,
+    options: ScrapeOptions,
+    progress: &dyn ProgressSink,
+) -> Result<ScrapeRun, String> {
+    let result = match options.record_dir.clone().filter(|d| !d.is_empty()) {
+        Some(dir) => record(queries, platform, limit, options, Path::new(&dir), progress).await,
+        None if options.cache_ttl_secs > 0 => run_cached(queries, platform, limit, options, progress).await,
+        None => run(queries, platform, limit, options, progress).await,
+    };
+    progress.emit(ProgressEvent::Done);
+    result
+}
+
+// `run_platform` for the shops one platform found in an earlier run: only the queries
+// they're missing are searched, then their product pages are visited like in a full run.
+// When the search can't start, the shops come back as they were and the platform is
+// reported as failed.
+#[allow(clippy::too_many_arguments)]
+async fn extend_platform(
+    platform: &str,
+    profile_path: Option<String>,
+    shops: Vec<ShopResults>,
+    queries: &[SearchQuery],
+    limit: usize,
+    options: &ScrapeOptions,
+    estimator: Option<&ShippingEstimator>,
+    progress: &dyn ProgressSink,
+) -> (Vec<ShopResults>, PlatformReport) {
+    let sel = Selectors::load(platform);
+    let waits = Waits::from_options(options);
+    let fetch_weights = estimator.is_some_and(|e| e.config().fetch_weights);
+
+    let (shops, status) = match BrowserSession::start(profile_path).await {
+        Ok(session) => {
+            let driver = &session.driver;
+            let earlier = shops.clone();
+            let result = match platform {
+                "tokopedia" => TokopediaScraper::extend(driver, shops, queries, progress, limit, options, &sel).await,
+                _ => ShopeeScraper::extend(driver, shops, queries, progress, limit, options, &sel).await,
+            };
+            let (mut shops, status) = match result {
+                Ok(shops) => (shops, PlatformStatus::Ok),
+                Err(reason) => {
+                    warn!("{} extension failed: {}", platform, reason);
+                    (earlier, PlatformStatus::Failed { reason })
+                }
+            };
+
+            if options.deep_scrape {
+                scraper::deep_scrape_products(driver, &mut shops, queries, &sel, &waits).await;
+            }
+            if fetch_weights {
+                scraper::fill_basket_weights(driver, &mut shops, &sel, &waits).await;
+            }
+            session.close().await;
+            (shops, status)
+        }
+        Err(reason) => {
+            warn!("{} browser failed to start: {}", platform, reason);
+            (shops, PlatformStatus::Failed { reason })
+        }
+    };
+
+    let report = PlatformReport {
+        platform: platform.to_string(),
+        status,
+        shops: shops.len(),
+    };
+    info!("{} extended: {:?}", platform, report.status);
+    progress.emit(ProgressEvent::PlatformFinished {
+        report: report.clone(),
+    });
+    (shops, report)
+}
+
+async fn extend(
+    shops: Vec<ShopResults>,
+    queries: Vec<SearchQuery>,
+    limit: usize,
+    options: ScrapeOptions,
+    progress: &dyn ProgressSink,
+) -> Result<ScrapeRun, String> {
+    let max_sessions = options.max_sessions.max(1);
+    let estimator = ShippingEstimator::from_config(shipping::load_config())?;
+
+    // Shops of other platforms are kept as they are
+    let mut by_platform: BTreeMap<&str, Vec<ShopResults>> = BTreeMap::new();
+    let mut kept: Vec<ShopResults> = Vec::new();
+    for shop in shops {
+        match platforms_for(&shop.platform).as_deref() {
+            Ok([platform]) => by_platform.entry(*platform).or_default().push(shop),
+            _ => kept.push(shop),
+        }
+    }
+    let platforms: Vec<&str> = by_platform.keys().copied().collect();
+
+    let mut run = ScrapeRun {
+        results: Vec::new(),
+        platforms: Vec::new(),
+    };
+    if !platforms.is_empty() {
+        let profiles = session_profiles(&platforms, max_sessions, scraper::get_chrome_profile_path());
+        let progress = AggregatingSink::new(progress, &platforms);
+        let progress = &progress;
+        let estimator_ref = estimator.as_ref();
+        let queries = &queries;
+        let options = &options;
+
+        let sessions: Vec<_> = by_platform
+            .into_iter()
+            .zip(profiles)
+            .map(|((platform, shops), profile)| {
+                extend_platform(platform, profile, shops, queries, limit, options, estimator_ref, progress)
+            })
+            .collect();
+        let outcomes: Vec<(Vec<ShopResults>, PlatformReport)> =
+            stream::iter(sessions).buffered(max_sessions).collect().await;
+        for (shops, report) in outcomes {
+            run.results.extend(shops);
+            run.platforms.push(report);
+        }
+    }
+    run.results.extend(kept);
+    filter::drop_low_rated_shops(&mut run.results);
+
+    // The new queries change what the cheapest basket is
+    for shop in run.results.iter_mut() {
+        shop.basket = Some(basket::summarize(shop, estimator.as_ref()));
+    }
+
+    Ok(run)
+}
+
+/// Searches `queries` in the shops of an earlier run (`shops`, e.g. the results of
+/// `scrape_products`) without looking for new shops: the forgotten item of a shopping
+/// list costs one search per shop instead of a new run. Queries a shop already has
+/// results for are skipped. Each platform gets its own browser session as in
+/// `scrape_products`, and progress is reported the same way, `ProgressEvent::Done`
+/// included. Returns every shop, with the new results added and the baskets redone.
+pub async fn extend_scrape(
+    shops: Vec<ShopResults>,
+    queries: Vec<SearchQuery>,
+    limit: usize,
+    mut options: ScrapeOptions,
+    progress: &dyn ProgressSink,
+) -> Result<ScrapeRun, String> {
+    let cache = (options.cache_ttl_secs > 0)
+        .then(|| Arc::new(ResultCache::load(options.cache_ttl_secs, options.refresh_cache)));
+    options.cache = cache.clone();
+
+    let result = extend(shops, queries, limit, options, progress).await;
+    if let Some(cache) = cache {
+        if let Err(e) = cache.save() {
+            warn!("{}", e);
+        }
+    }
+    progress.emit(ProgressEvent::Done);
+    result
+}
+
+/// A replayed session and how it compares to the recording.
+pub struct ReplayOutcome {
+    pub run: ScrapeRun,
+    // Shop URLs whose results differ from the recorded ones, or that only one of
+    // the two runs found
+    pub differences: Vec<String>,
+}
+
+// Shops by URL, without the basket summary: it depends on the shipping settings of
+// the machine replaying the session
+fn comparable(shops: &[ShopResults]) -> BTreeMap<String, serde_json::Value> {
+    shops
+        .iter()
+        .map(|shop| {
+            let mut shop = shop.clone();
+            shop.basket = None;
+            let key = format!("{} {}", shop.platform, shop.shop_url);
+            (key, serde_json::to_value(&shop).unwrap_or_default())
+        })
+        .collect()
+}
+
+/// Runs the scrape recorded in `dir` again, against its saved pages instead of the
+/// live marketplaces, and lists the shops whose results came out differently.
+pub async fn replay_session(dir: &Path, progress: &dyn ProgressSink) -> Result<ReplayOutcome, String> {
+    let archive = Arc::new(SessionArchive::open(dir)?);
+    let recorded = archive.recorded_results()?;
+    let info = archive.info();
+    let _server = archive.serve().await?;
+
+    let mut options = info.options;
+    options.archive = Some(Arc::clone(&archive));
+    let result = run(info.queries, info.platform, info.limit, options, progress).await;
+    progress.emit(ProgressEvent::Done);
+    let run = result?;
+
+    let expected = comparable(&recorded);
+    let actual = comparable(&run.results);
+    let differences: Vec<String> = expected
+        .keys()
+        .chain(actual.keys())
+        .filter(|key| expected.get(*key) != actual.get(*key))
+        .cloned()
+        .collect::<BTreeSet<String>>()
+        .into_iter()
+        .collect();
+    info!("Replay finished, {} shop(s) differ", differences.len());
+    Ok(ReplayOutcome { run, differences })
+}
@@ -0,0 +1,288 @@
+// Selector health check: one canned search per platform with every selector counted,
+// so a markup change shows up as a failed check instead of a scrape that silently
+// finds nothing. A failed check leaves the page source and a screenshot in the log
+// directory to attach to a bug report.
+
+use chrono::Local;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use thirtyfour::prelude::*;
+
+use crate::browser::Browser;
+use crate::navigate::open_page;
+use crate::scraper;
+use crate::selectors::Selectors;
+use crate::session::BrowserSession;
+use crate::wait::{stable_count, Waits};
+
+// Common enough to have results on every platform
+const CANNED_QUERY: &str = "kabel";
+
+// Cards checked for the card-level selectors
+const SAMPLE_CARDS: usize = 10;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Stage {
+    // Platform homepage
+    Home,
+    // Search results page
+    Search,
+    // Inside the result cards
+    Card,
+}
+
+impl Stage {
+    fn name(self) -> &'static str {
+        match self {
+            Stage::Home => "home",
+            Stage::Search => "search",
+            Stage::Card => "card",
+        }
+    }
+}
+
+// Selector key, where it is looked up, and whether a scrape needs it. Load-more and
+// next-page buttons are missing whenever the results fit on one page.
+const TOKOPEDIA_CHECKS: &[(&str, Stage, bool)] = &[
+    ("search_input", Stage::Home, true),
+    ("seed_card", Stage::Search, true),
+    ("load_more", Stage::Search, false),
+    ("card_name", Stage::Card, true),
+    ("card_price", Stage::Card, true),
+    ("card_shop", Stage::Card, true),
+    ("card_location", Stage::Card, true),
+    ("card_photo", Stage::Card, true),
+];
+
+const SHOPEE_CHECKS: &[(&str, Stage, bool)] = &[
+    ("search_input", Stage::Home, true),
+    ("search_item", Stage::Search, true),
+    ("seed_card", Stage::Search, true),
+    ("next_page", Stage::Search, false),
+    ("card_name", Stage::Card, true),
+    ("card_price", Stage::Card, true),
+    ("card_location", Stage::Card, true),
+    ("card_photo", Stage::Card, true),
+];
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SelectorCheck {
+    pub key: String,
+    // "home", "search" or "card"
+    pub page: String,
+    pub required: bool,
+    // Elements found on the page; for card selectors, cards with a match
+    pub count: usize,
+    // Position in the fallback chain of the rule that matched
+    pub matched_rule: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DiagnosticReport {
+    pub platform: String,
+    pub query: String,
+    pub selectors_version: u32,
+    pub selectors_override: Option<String>,
+    // Pages that failed to load
+    pub errors: Vec<String>,
+    pub cards_sampled: usize,
+    pub checks: Vec<SelectorCheck>,
+    pub healthy: bool,
+    // Folder with page source, screenshot and this report, when unhealthy
+    pub bundle: Option<String>,
+}
+
+fn urls(platform: &str) -> (&'static str, String) {
+    let query = urlencoding::encode(CANNED_QUERY);
+    match platform {
+        "shopee" => (
+            "https://shopee.co.id/",
+            format!("https://shopee.co.id/search?keyword={}", query),
+        ),
+        _ => (
+            "https://www.tokopedia.com/",
+            format!("https://www.tokopedia.com/search?q={}", query),
+        ),
+    }
+}
+
+async fn check_page(
+    driver: &WebDriver,
+    sel: &Selectors,
+    checks: &[(&str, Stage, bool)],
+    page: Stage,
+) -> Vec<SelectorCheck> {
+    let mut found = Vec::new();
+    for &(key, stage, required) in checks {
+        if stage != page {
+            continue;
+        }
+        let (count, matched_rule) = sel.count(driver, key).await;
+        found.push(SelectorCheck {
+            key: key.to_string(),
+            page: page.name().to_string(),
+            required,
+            count,
+            matched_rule,
+        });
+    }
+    found
+}
+
+async fn check_cards(
+    cards: &[WebElement],
+    sel: &Selectors,
+    checks: &[(&str, Stage, bool)],
+) -> Vec<SelectorCheck> {
+    let mut found = Vec::new();
+    for &(key, stage, required) in checks {
+        if stage != Stage::Card {
+            continue;
+        }
+        let mut count = 0;
+        let mut matched_rule: Option<usize> = None;
+        for card in cards {
+            if let (_, Some(rule)) = sel.count(card, key).await {
+                count += 1;
+                matched_rule = Some(matched_rule.map_or(rule, |r| r.min(rule)));
+            }
+        }
+        found.push(SelectorCheck {
+            key: key.to_string(),
+            page: Stage::Card.name().to_string(),
+            required,
+            count,
+            matched_rule,
+        });
+    }
+    found
+}
+
+// Saves page source, screenshot and report into a new folder under `log_dir`
+async fn save_bundle(
+    driver: &WebDriver,
+    log_dir: &Path,
+    report: &DiagnosticReport,
+) -> Result<PathBuf, String> {
+    let dir = log_dir.join("diagnostics").join(format!(
+        "{}-{}",
+        report.platform,
+        Local::now().format("%Y%m%d_%H%M%S")
+    ));
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create diagnostics folder: {}", e))?;
+
+    let source = driver
+        .source()
+        .await
+        .map_err(|e| format!("Failed to read page source: {}", e))?;
+    fs::write(dir.join("page.html"), source)
+        .map_err(|e| format!("Failed to write page source: {}", e))?;
+    if let Err(e) = Browser::screenshot(driver, &dir.join("screenshot.png")).await {
+        // The source alone still shows what changed
+        warn!("{}", e);
+    }
+
+    let mut report = report.clone();
+    report.bundle = Some(dir.to_string_lossy().to_string());
+    let json = serde_json::to_string_pretty(&report)
+        .map_err(|e| format!("Failed to serialize report: {}", e))?;
+    fs::write(dir.join("report.json"), json)
+        .map_err(|e| format!("Failed to write report: {}", e))?;
+    Ok(dir)
+}
+
+/// Runs a canned search on `platform` and checks every selector the scraper needs.
+/// When a required selector matches nothing or a page doesn't load, page source and
+/// screenshot are saved under `<log_dir>/diagnostics/`. Only a browser that can't be
+/// started is an error.
+pub async fn diagnose_platform(platform: &str, log_dir: &Path) -> Result<DiagnosticReport, String> {
+    let checks = match platform {
+        "tokopedia" => TOKOPEDIA_CHECKS,
+        "shopee" => SHOPEE_CHECKS,
+        _ => return Err("Unsupported platform".to_string()),
+    };
+    let sel = Selectors::load(platform);
+    let waits = Waits::default();
+    let (home_url, search_url) = urls(platform);
+
+    // Shopee only shows results when logged in, so use the configured profile
+    let session = BrowserSession::start(Some(scraper::get_chrome_profile_path())).await?;
+    let driver = &session.driver;
+
+    let mut report = DiagnosticReport {
+        platform: platform.to_string(),
+        query: CANNED_QUERY.to_string(),
+        selectors_version: sel.version,
+        selectors_override: sel
+            .overridden_by
+            .as_ref()
+            .map(|p| p.to_string_lossy().to_string()),
+        errors: Vec::new(),
+        cards_sampled: 0,
+        checks: Vec::new(),
+        healthy: false,
+        bundle: None,
+    };
+
+    let home_ready = match platform {
+        "shopee" => sel.css("home_ready"),
+        _ => sel.css("search_input"),
+    };
+    if let Err(e) = open_page(driver, home_url, &[&home_ready], &waits).await {
+        report.errors.push(e);
+    }
+    report
+        .checks
+        .extend(check_page(driver, &sel, checks, Stage::Home).await);
+
+    let (card_key, empty_key) = match platform {
+        "shopee" => ("search_item", "search_empty"),
+        _ => ("seed_card", "empty_state"),
+    };
+    let card = sel.css(card_key);
+    let empty = sel.css(empty_key);
+    match open_page(driver, &search_url, &[card.as_str(), empty.as_str()], &waits).await {
+        Ok(0) => {
+            // Load-more and next-page buttons show up at the bottom
+            let _ = driver
+                .execute("window.scrollTo(0, document.body.scrollHeight);", vec![])
+                .await;
+            stable_count(driver, &card, &waits).await;
+        }
+        Ok(_) => report
+            .errors
+            .push(format!("No results for \"{}\"", CANNED_QUERY)),
+        Err(e) => report.errors.push(e),
+    }
+    report
+        .checks
+        .extend(check_page(driver, &sel, checks, Stage::Search).await);
+
+    let mut cards = sel.find_all(driver, "seed_card").await;
+    cards.truncate(SAMPLE_CARDS);
+    report.cards_sampled = cards.len();
+    report.checks.extend(check_cards(&cards, &sel, checks).await);
+
+    report.healthy = report.errors.is_empty()
+        && report.checks.iter().all(|c| !c.required || c.count > 0);
+    info!(
+        "{} selector check: {}",
+        platform,
+        if report.healthy { "healthy" } else { "failing" }
+    );
+
+    if !report.healthy {
+        match save_bundle(driver, log_dir, &report).await {
+            Ok(dir) => {
+                info!("Diagnostics saved to {}", dir.display());
+                report.bundle = Some(dir.to_string_lossy().to_string());
+            }
+            Err(e) => warn!("{}", e),
+        }
+    }
+    session.close().await;
+
+    Ok(report)
+}
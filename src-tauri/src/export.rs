@@ -0,0 +1,310 @@
+// Exporters shared by the app (Excel button) and the command-line binary
+
+use rust_xlsxwriter::Workbook;
+use std::fs;
+use std::path::Path;
+
+use crate::basket;
+use crate::models::{Product, QueryResult, QueryStatus, ShopResults};
+use crate::parse::parse_price;
+
+const HEADERS: [&str; 22] = [
+    "Nama Toko",
+    "Platform",
+    "URL Toko",
+    "Query",
+    "Nama Produk",
+    "Harga",
+    "Harga Asli",
+    "Diskon",
+    "Rating",
+    "Terjual",
+    "Badge",
+    "Stok",
+    "Min. Order",
+    "Varian",
+    "Lokasi",
+    "Link Produk",
+    "Jumlah",
+    "Subtotal",
+    "Masuk Keranjang",
+    "Status",
+    "Keterangan",
+    "Cache",
+];
+
+// Position of a column in `HEADERS`
+fn column(header: &str) -> usize {
+    HEADERS
+        .iter()
+        .position(|h| *h == header)
+        .expect("export column exists")
+}
+
+// "Official Store, Star Seller" for exports
+pub fn badge_labels(product: &Product) -> String {
+    product
+        .badges
+        .iter()
+        .map(|b| b.label())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn platform_label(platform: &str) -> &'static str {
+    if platform == "tokopedia" {
+        "Tokopedia"
+    } else {
+        "Shopee"
+    }
+}
+
+// Whether a query's cells can be trusted, as the app shows it
+fn status_label(status: QueryStatus) -> &'static str {
+    match status {
+        QueryStatus::Ok => "OK",
+        QueryStatus::Empty => "Tidak ada hasil",
+        QueryStatus::Failed => "Gagal dimuat",
+    }
+}
+
+// Status, error and result cache age of a query, the last columns of its rows
+fn status_cells(query_result: &QueryResult) -> [String; 3] {
+    let age = query_result.cached_age_secs.map(|secs| match secs {
+        0..=59 => "baru saja".to_string(),
+        60..=3599 => format!("{} menit lalu", secs / 60),
+        _ => format!("{} jam lalu", secs / 3600),
+    });
+    [
+        status_label(query_result.status).to_string(),
+        query_result.error.clone().unwrap_or_default(),
+        age.unwrap_or_default(),
+    ]
+}
+
+// One row per product, a row without a product for queries that found none (or
+// failed to load), followed by a TOTAL row per shop when the basket is known
+fn rows(results: &[ShopResults]) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+
+    for shop in results {
+        let chosen = basket::cheapest_indices(shop);
+        for (query_result, chosen_index) in shop.results.iter().zip(chosen) {
+            if query_result.products.is_empty() {
+                let mut row = vec![String::new(); HEADERS.len()];
+                row[column("Nama Toko")] = shop.shop_name.clone();
+                row[column("Platform")] = platform_label(&shop.platform).to_string();
+                row[column("URL Toko")] = shop.shop_url.clone();
+                row[column("Query")] = query_result.query.clone();
+                row[column("Jumlah")] = query_result.quantity.to_string();
+                row.truncate(column("Status"));
+                row.extend(status_cells(query_result));
+                rows.push(row);
+            }
+            for (product_index, product) in query_result.products.iter().enumerate() {
+                let detail = product.detail.clone().unwrap_or_default();
+                let in_basket = chosen_index == Some(product_index);
+                // Same quantity the basket buys, so the subtotals add up to its total
+                let quantity = basket::effective_quantity(product, query_result.quantity);
+                let subtotal = parse_price(&product.price)
+                    .map(|price| price.saturating_mul(u64::from(quantity)).to_string())
+                    .unwrap_or_default();
+                let mut row = vec![
+                    shop.shop_name.clone(),
+                    platform_label(&shop.platform).to_string(),
+                    shop.shop_url.clone(),
+                    query_result.query.clone(),
+                    product.name.clone(),
+                    product.price.clone(),
+                    product.original_price.clone().unwrap_or_default(),
+                    product.discount_percent.map(|d| format!("{}%", d)).unwrap_or_default(),
+                    product.rating.map(|r| r.to_string()).unwrap_or_default(),
+                    product.sold_count.map(|n| n.to_string()).unwrap_or_default(),
+                    badge_labels(product),
+                    detail.stock.map(|n| n.to_string()).unwrap_or_default(),
+                    detail.min_order.map(|n| n.to_string()).unwrap_or_default(),
+                    detail
+                        .variants
+                        .iter()
+                        .map(|v| match &v.price {
+                            Some(price) => format!("{} ({})", v.name, price),
+                            None => v.name.clone(),
+                        })
+                        .collect::<Vec<_>>()
+                        .join("; "),
+                    product.location.clone(),
+                    product.link.clone(),
+                    quantity.to_string(),
+                    subtotal,
+                    if in_basket { "Ya" } else { "" }.to_string(),
+                ];
+                row.extend(status_cells(query_result));
+                rows.push(row);
+            }
+        }
+
+        // Per-shop basket total below the shop's products
+        if let Some(basket) = &shop.basket {
+            let mut total = vec![String::new(); HEADERS.len()];
+            total[column("Nama Toko")] = shop.shop_name.clone();
+            total[column("Query")] = "TOTAL".to_string();
+            total[column("Nama Produk")] = basket
+                .shipping
+                .as_ref()
+                .map(|s| format!("ongkir {} ({})", s.cost, s.courier))
+                .unwrap_or_default();
+            total[column("Subtotal")] = basket.total.to_string();
+            rows.push(total);
+        }
+    }
+
+    rows
+}
+
+pub fn to_csv(results: &[ShopResults]) -> Result<String, String> {
+    let mut writer = csv::WriterBuilder::new()
+        .quote_style(csv::QuoteStyle::Always)
+        .from_writer(Vec::new());
+    writer
+        .write_record(HEADERS)
+        .map_err(|e| format!("Failed to write CSV: {}", e))?;
+    for row in rows(results) {
+        writer
+            .write_record(&row)
+            .map_err(|e| format!("Failed to write CSV: {}", e))?;
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| format!("Failed to write CSV: {}", e))?;
+    String::from_utf8(bytes).map_err(|e| format!("Failed to write CSV: {}", e))
+}
+
+pub fn write_csv(results: &[ShopResults], path: &Path) -> Result<(), String> {
+    fs::write(path, to_csv(results)?).map_err(|e| format!("Failed to write CSV file: {}", e))
+}
+
+pub fn write_json(results: &[ShopResults], path: &Path) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(results)
+        .map_err(|e| format!("Failed to serialize results: {}", e))?;
+    fs::write(path, content).map_err(|e| format!("Failed to write JSON file: {}", e))
+}
+
+pub fn write_xlsx(results: &[ShopResults], path: &Path) -> Result<(), String> {
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    sheet
+        .set_name("Hasil Pencarian")
+        .map_err(|e| format!("Failed to create worksheet: {}", e))?;
+
+    for (col, header) in HEADERS.iter().enumerate() {
+        sheet
+            .write_string(0, col as u16, *header)
+            .map_err(|e| format!("Failed to write XLSX: {}", e))?;
+    }
+    for (row_index, row) in rows(results).iter().enumerate() {
+        for (col, value) in row.iter().enumerate() {
+            sheet
+                .write_string(row_index as u32 + 1, col as u16, value)
+                .map_err(|e| format!("Failed to write XLSX: {}", e))?;
+        }
+    }
+
+    workbook
+        .save(path)
+        .map_err(|e| format!("Failed to write XLSX file: {}", e))
+}
+
+/// Output format from the file extension: `json`, `csv` or `xlsx`.
+pub fn output_format(path: &Path) -> Result<String, String> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    match extension.as_str() {
+        "json" | "csv" | "xlsx" => Ok(extension),
+        _ => Err(format!(
+            "Unsupported output format '{}' (use .json, .csv or .xlsx)",
+            path.display()
+        )),
+    }
+}
+
+pub fn write_results(results: &[ShopResults], path: &Path) -> Result<(), String> {
+    match output_format(path)?.as_str() {
+        "json" => write_json(results, path),
+        "csv" => write_csv(results, path),
+        _ => write_xlsx(results, path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ProductDetail, SearchQuery};
+
+    #[test]
+    fn queries_without_products_keep_their_row() {
+        let query = SearchQuery::from("2x fitting lampu");
+        let product = Product {
+            name: "Kabel NYM 2x1.5".to_string(),
+            price: "Rp450.000".to_string(),
+            ..Default::default()
+        };
+        let mut found = QueryResult::new(&SearchQuery::from("kabel nym"), vec![product]);
+        found.cached_age_secs = Some(7200);
+        let shop = ShopResults {
+            shop_name: "Toko Listrik Jaya".to_string(),
+            shop_url: "https://www.tokopedia.com/tokolistrikjaya".to_string(),
+            platform: "tokopedia".to_string(),
+            results: vec![found, QueryResult::failed(&query, "timeout".to_string())],
+            profile: None,
+            basket: None,
+        };
+
+        let rows = rows(&[shop]);
+        assert_eq!(rows.len(), 2);
+        assert!(rows.iter().all(|row| row.len() == HEADERS.len()));
+        assert_eq!(rows[0][column("Status")], "OK");
+        assert_eq!(rows[0][column("Cache")], "2 jam lalu");
+
+        let failed = &rows[1];
+        assert_eq!(failed[column("Query")], "fitting lampu");
+        assert_eq!(failed[column("Nama Produk")], "");
+        assert_eq!(failed[column("Jumlah")], "2");
+        assert_eq!(failed[column("Status")], "Gagal dimuat");
+        assert_eq!(failed[column("Keterangan")], "timeout");
+    }
+
+    #[test]
+    fn subtotals_use_the_quantity_the_basket_buys() {
+        let product = Product {
+            name: "Fitting Lampu E27".to_string(),
+            price: "Rp5.000".to_string(),
+            detail: Some(ProductDetail {
+                min_order: Some(10),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut shop = ShopResults {
+            shop_name: "Toko Listrik Jaya".to_string(),
+            shop_url: "https://www.tokopedia.com/tokolistrikjaya".to_string(),
+            platform: "tokopedia".to_string(),
+            results: vec![QueryResult::new(
+                &SearchQuery::from("2x fitting lampu"),
+                vec![product],
+            )],
+            profile: None,
+            basket: None,
+        };
+        shop.basket = Some(basket::summarize(&shop, None));
+
+        let rows = rows(&[shop]);
+        assert_eq!(rows[0][column("Jumlah")], "10");
+        assert_eq!(rows[0][column("Subtotal")], "50000");
+        let total = &rows[1];
+        assert_eq!(total[column("Query")], "TOTAL");
+        assert_eq!(total[column("Subtotal")], "50000");
+    }
+}
@@ -0,0 +1,313 @@
+// Product cards read from HTML instead of one WebDriver call per field: the live
+// scrapers pass each card's outerHTML, the tests pages saved under `tests/fixtures`.
+// Only the CSS rules of a selector chain apply here; XPath rules are skipped.
+
+use kuchikiki::traits::*;
+use kuchikiki::NodeRef;
+use log::warn;
+
+use crate::models::{Product, QueryFilter, ShopProfile};
+use crate::parse::{parse_card_stats, parse_result_total, parse_shop_profile, CardStats};
+use crate::selectors::Selectors;
+
+// Text of these elements is never shown on the page
+const HIDDEN: [&str; 3] = ["script", "style", "noscript"];
+
+/// Parses a saved page or a single card's outerHTML.
+pub fn parse_html(html: &str) -> NodeRef {
+    kuchikiki::parse_html().one(html).document_node
+}
+
+/// Elements inside `scope` matched by the first CSS rule of `key` that matches any.
+pub fn select_all(scope: &NodeRef, sel: &Selectors, key: &str) -> Vec<NodeRef> {
+    for css in sel.chain(key).iter().filter_map(|rule| rule.css.as_deref()) {
+        let found: Vec<NodeRef> = match scope.descendants().select(css) {
+            Ok(matches) => matches.map(|m| m.as_node().clone()).collect(),
+            Err(()) => {
+                warn!("Invalid {} selector {}: {}", sel.platform, key, css);
+                continue;
+            }
+        };
+        if !found.is_empty() {
+            return found;
+        }
+    }
+    Vec::new()
+}
+
+/// Text (or the rule's attribute) of the first element matched by `key`.
+pub fn read(scope: &NodeRef, sel: &Selectors, key: &str) -> Option<String> {
+    for rule in sel.chain(key) {
+        let Some(css) = rule.css.as_deref() else {
+            continue;
+        };
+        let Some(node) = scope
+            .descendants()
+            .select(css)
+            .ok()
+            .and_then(|mut matches| matches.next())
+        else {
+            continue;
+        };
+        let node = node.as_node();
+        return Some(match &rule.attr {
+            Some(name) => attr(node, name).unwrap_or_default(),
+            None => text(node),
+        });
+    }
+    None
+}
+
+fn read_text(scope: &NodeRef, sel: &Selectors, key: &str) -> String {
+    read(scope, sel, key).unwrap_or_default()
+}
+
+pub fn attr(node: &NodeRef, name: &str) -> Option<String> {
+    let element = node.as_element()?;
+    let attributes = element.attributes.borrow();
+    attributes.get(name).map(|v| v.to_string())
+}
+
+/// Text of an element with whitespace collapsed, like the browser shows it inline.
+pub fn text(node: &NodeRef) -> String {
+    node.text_contents().split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Visible text with every text node on its own line: close enough to the browser's
+/// innerText for the line-based parsers in `parse`.
+pub fn text_lines(node: &NodeRef) -> String {
+    node.descendants()
+        .text_nodes()
+        .filter(|t| {
+            let parent = t.as_node().parent();
+            let tag = parent.as_ref().and_then(|p| p.as_element()).map(|e| &*e.name.local);
+            !tag.is_some_and(|tag| HIDDEN.contains(&tag))
+        })
+        .map(|t| t.borrow().split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|t| !t.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Card text and image alts, so rating, sold count, discount and badges can be
+// picked out without a dedicated selector per field
+fn card_stats(card: &NodeRef, sel: &Selectors, price: &str) -> CardStats {
+    let labels: Vec<String> = select_all(card, sel, "badge_images")
+        .iter()
+        .filter_map(|img| attr(img, "alt"))
+        .collect();
+    parse_card_stats(&text_lines(card), price, &labels)
+}
+
+/// Tokopedia card (the product link). `None` for links that aren't products, such
+/// as "see all" links to a shop's product list.
+pub fn tokopedia_card(card: &NodeRef, sel: &Selectors) -> Option<Product> {
+    let link = attr(card, "href").unwrap_or_default();
+    if link.contains("/product?perpage") {
+        return None;
+    }
+    let link = if link.starts_with("//") {
+        format!("https:{}", link)
+    } else if link.starts_with('/') {
+        format!("https://www.tokopedia.com{}", link)
+    } else {
+        link
+    };
+
+    // The name is the first non-empty span of the title block
+    let name = select_all(card, sel, "card_name")
+        .iter()
+        .take(20)
+        .map(text)
+        .find(|t| !t.is_empty())
+        .unwrap_or_default();
+
+    let mut product = Product {
+        name,
+        price: read_text(card, sel, "card_price"),
+        shop: read_text(card, sel, "card_shop"),
+        location: read_text(card, sel, "card_location"),
+        photo: read_text(card, sel, "card_photo"),
+        link,
+        ..Default::default()
+    };
+    card_stats(card, sel, &product.price).apply(&mut product);
+    Some(product)
+}
+
+/// Shopee card: the product link itself (search results) or an element holding it
+/// (shop search). `None` for "find similar" links and cards without a price. The
+/// shop is the shop id from the product link, empty when the link has none.
+pub fn shopee_card(card: &NodeRef, sel: &Selectors) -> Option<Product> {
+    let link = match attr(card, "href") {
+        Some(href) => href,
+        None => select_all(card, sel, "card_link")
+            .first()
+            .and_then(|a| attr(a, "href"))?,
+    };
+    if !link.starts_with('/') || link.contains("find_similar_products") {
+        return None;
+    }
+    let link = format!("https://shopee.co.id{}", link);
+
+    let price = read(card, sel, "card_price")?;
+    let mut product = Product {
+        name: read_text(card, sel, "card_name"),
+        price: format!("Rp{}", price),
+        shop: shopee_shop_id(&link).unwrap_or_default(),
+        location: read_text(card, sel, "card_location"),
+        photo: read_text(card, sel, "card_photo"),
+        link,
+        ..Default::default()
+    };
+    card_stats(card, sel, &product.price).apply(&mut product);
+    Some(product)
+}
+
+/// Card of `sel.platform` from its outerHTML.
+pub fn card_from_html(html: &str, sel: &Selectors) -> Option<Product> {
+    let document = parse_html(html);
+    let card = document.select_first("body > *").ok()?;
+    match sel.platform.as_str() {
+        "shopee" => shopee_card(card.as_node(), sel),
+        _ => tokopedia_card(card.as_node(), sel),
+    }
+}
+
+/// Cards of `sel.platform` matched by `key` on a whole page.
+pub fn page_cards(html: &str, sel: &Selectors, key: &str) -> Vec<Product> {
+    let document = parse_html(html);
+    select_all(&document, sel, key)
+        .iter()
+        .filter_map(|card| match sel.platform.as_str() {
+            "shopee" => shopee_card(card, sel),
+            _ => tokopedia_card(card, sel),
+        })
+        .collect()
+}
+
+/// Size of a search result, for picking the rarest seed query.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ResultCount {
+    pub total: u64,
+    // Only the cards of the first page were counted; the result may be larger
+    pub at_least: bool,
+}
+
+/// Size of a search result: the total the page states (Tokopedia), else the number of
+/// result pages times the `seed_card`s on the first one that satisfy `filter`
+/// (Shopee states no total, only its page count), else just those cards.
+pub fn result_count(html: &str, sel: &Selectors, filter: &QueryFilter) -> ResultCount {
+    let document = parse_html(html);
+    if let Some(total) = parse_result_total(&text_lines(&document)) {
+        return ResultCount { total, at_least: false };
+    }
+    let cards = page_cards(html, sel, "seed_card")
+        .iter()
+        .filter(|product| filter.matches(product))
+        .count() as u64;
+    let pages = match sel.platform.as_str() {
+        "shopee" => read(&document, sel, "result_pages").and_then(|t| t.trim().parse::<u64>().ok()),
+        _ => None,
+    };
+    match pages {
+        Some(pages) => ResultCount {
+            total: cards * pages.max(1),
+            at_least: false,
+        },
+        None => ResultCount {
+            total: cards,
+            at_least: cards > 0,
+        },
+    }
+}
+
+/// Index of the first of `selectors` (CSS) that matches in `html`.
+pub fn find_any(html: &str, selectors: &[&str]) -> Option<usize> {
+    let document = parse_html(html);
+    selectors
+        .iter()
+        .position(|css| document.select_first(css).is_ok())
+}
+
+/// Profile from a Tokopedia shop page: the block four divs above the shop name, the
+/// same one the browser's `shop_profile` XPath picks.
+pub fn tokopedia_shop_profile(html: &str, sel: &Selectors) -> ShopProfile {
+    let document = parse_html(html);
+    let Some(header) = select_all(&document, sel, "shop_header").into_iter().next() else {
+        return ShopProfile::default();
+    };
+    let divs: Vec<NodeRef> = header
+        .ancestors()
+        .filter(|node| node.as_element().is_some_and(|e| &*e.name.local == "div"))
+        .take(4)
+        .collect();
+    let block = divs.last().unwrap_or(&header);
+    let labels: Vec<String> = select_all(block, sel, "badge_images")
+        .iter()
+        .filter_map(|img| attr(img, "alt"))
+        .collect();
+    parse_shop_profile(&text_lines(block), &labels)
+}
+
+/// Shop and item id of a Shopee product.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShopeeIds {
+    pub shop_id: String,
+    pub item_id: String,
+}
+
+/// Ids from a Shopee product link, absolute or relative, in either of its forms:
+///   /Celana-Pendek-i.124455053.29705222804?sp_atk=... -> 124455053, 29705222804
+///   /product/124455053/29705222804                    -> 124455053, 29705222804
+pub fn shopee_ids(link: &str) -> Option<ShopeeIds> {
+    let path = link
+        .split(['?', '#'])
+        .next()
+        .unwrap_or(link)
+        .trim_end_matches('/');
+    let (shop_id, item_id) = match path.rsplit_once("-i.") {
+        Some((_, ids)) => ids.split_once('.')?,
+        None => {
+            let mut parts = path.rsplit('/');
+            let item_id = parts.next()?;
+            let shop_id = parts.next()?;
+            if parts.next() != Some("product") {
+                return None;
+            }
+            (shop_id, item_id)
+        }
+    };
+    let is_id = |id: &str| !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit());
+    if !is_id(shop_id) || !is_id(item_id) {
+        return None;
+    }
+    Some(ShopeeIds {
+        shop_id: shop_id.to_string(),
+        item_id: item_id.to_string(),
+    })
+}
+
+/// Shop id from a Shopee product link.
+pub fn shopee_shop_id(link: &str) -> Option<String> {
+    shopee_ids(link).map(|ids| ids.shop_id)
+}
+
+/// Shop page for a Shopee shop id; Shopee redirects it to the shop's own address.
+pub fn shopee_shop_url(shop_id: &str) -> String {
+    format!("https://shopee.co.id/shop/{}", shop_id)
+}
+
+/// Shop id back from a `shopee_shop_url`, e.g. of saved results.
+pub fn shopee_shop_id_from_url(shop_url: &str) -> Option<String> {
+    let id = shop_url.strip_prefix("https://shopee.co.id/shop/")?;
+    let id = id.split(['?', '#', '/']).next().unwrap_or_default();
+    (!id.is_empty() && id.bytes().all(|b| b.is_ascii_digit())).then(|| id.to_string())
+}
+
+/// Shop slug from a Tokopedia shop page URL, `https://www.tokopedia.com/<slug>`.
+pub fn tokopedia_shop_slug(shop_url: &str) -> Option<String> {
+    let slug = shop_url.strip_prefix("https://www.tokopedia.com/")?;
+    let slug = slug.split(['?', '#', '/']).next().unwrap_or_default();
+    (!slug.is_empty()).then(|| slug.to_string())
+}
@@ -0,0 +1,173 @@
+// In-memory browser for the platform tests: pages are HTML keyed by URL, searched
+// with kuchikiki's CSS engine. Page scripts don't run and the checks the waits poll
+// are answered from the DOM. XPath finds nothing, like a page without the element.
+
+use kuchikiki::traits::*;
+use kuchikiki::NodeRef;
+use serde_json::Value;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::browser::{Browser, Element, Locator, Scope};
+use crate::extract;
+use crate::wait::Page;
+
+/// Browser serving fixed pages. Any other URL opens an empty page.
+#[derive(Default)]
+pub(crate) struct FakeBrowser {
+    pages: HashMap<String, String>,
+    url: RefCell<String>,
+    document: RefCell<Option<NodeRef>>,
+    visited: RefCell<Vec<String>>,
+    // Set by `expect_navigation` until the next `navigate`: typing never submits
+    // here, so a typed search leaves the old page showing but never ready
+    leaving: Cell<bool>,
+}
+
+impl FakeBrowser {
+    /// Serves `html` at `url`.
+    pub fn page(mut self, url: &str, html: &str) -> Self {
+        self.pages.insert(url.to_string(), html.to_string());
+        self
+    }
+
+    /// Every URL navigated to, in order.
+    pub fn visited(&self) -> Vec<String> {
+        self.visited.borrow().clone()
+    }
+
+    fn document(&self) -> NodeRef {
+        self.document
+            .borrow()
+            .clone()
+            .unwrap_or_else(|| extract::parse_html(""))
+    }
+}
+
+fn select_all(scope: &NodeRef, locator: Locator<'_>) -> Vec<FakeElement> {
+    let Locator::Css(css) = locator else {
+        return Vec::new();
+    };
+    match scope.descendants().select(css) {
+        Ok(matches) => matches.map(|m| FakeElement(m.as_node().clone())).collect(),
+        Err(()) => Vec::new(),
+    }
+}
+
+impl Page for FakeBrowser {
+    async fn run(&self, _script: &str, _args: Vec<Value>) -> Option<Value> {
+        Some(Value::Null)
+    }
+
+    async fn navigate(&self, url: &str) -> Result<(), String> {
+        self.visited.borrow_mut().push(url.to_string());
+        let html = self.pages.get(url).map(String::as_str).unwrap_or("");
+        *self.url.borrow_mut() = url.to_string();
+        *self.document.borrow_mut() = Some(extract::parse_html(html));
+        self.leaving.set(false);
+        Ok(())
+    }
+
+    async fn expect_navigation(&self) {
+        self.leaving.set(true);
+    }
+
+    async fn find_any(&self, selectors: &[&str]) -> Option<usize> {
+        if self.leaving.get() {
+            return None;
+        }
+        let document = self.document();
+        selectors
+            .iter()
+            .position(|css| document.select_first(css).is_ok())
+    }
+
+    async fn count(&self, selector: &str) -> Option<i64> {
+        if self.leaving.get() {
+            return None;
+        }
+        Some(select_all(&self.document(), Locator::Css(selector)).len() as i64)
+    }
+
+    // Nothing is ever loading
+    async fn requests(&self) -> Option<i64> {
+        Some(0)
+    }
+
+    async fn problem(&self, check_blank: bool) -> Option<String> {
+        let blank = extract::text(&self.document()).is_empty();
+        (check_blank && blank).then(|| "blank page".to_string())
+    }
+}
+
+impl Scope for FakeBrowser {
+    type Element = FakeElement;
+
+    async fn find(&self, locator: Locator<'_>) -> Option<FakeElement> {
+        select_all(&self.document(), locator).into_iter().next()
+    }
+
+    async fn find_all(&self, locator: Locator<'_>) -> Vec<FakeElement> {
+        select_all(&self.document(), locator)
+    }
+}
+
+impl Browser for FakeBrowser {
+    async fn current_url(&self) -> String {
+        self.url.borrow().clone()
+    }
+
+    async fn screenshot(&self, _path: &Path) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Element of a `FakeBrowser` page. Clicking and typing change nothing.
+#[derive(Clone)]
+pub(crate) struct FakeElement(NodeRef);
+
+impl Scope for FakeElement {
+    type Element = FakeElement;
+
+    async fn find(&self, locator: Locator<'_>) -> Option<FakeElement> {
+        select_all(&self.0, locator).into_iter().next()
+    }
+
+    async fn find_all(&self, locator: Locator<'_>) -> Vec<FakeElement> {
+        select_all(&self.0, locator)
+    }
+}
+
+impl Element for FakeElement {
+    async fn attr(&self, name: &str) -> Option<String> {
+        extract::attr(&self.0, name)
+    }
+
+    // One line per text node, close to innerText
+    async fn text(&self) -> String {
+        extract::text_lines(&self.0)
+    }
+
+    async fn outer_html(&self) -> String {
+        self.0.to_string()
+    }
+
+    async fn is_displayed(&self) -> bool {
+        extract::attr(&self.0, "hidden").is_none()
+    }
+
+    async fn is_enabled(&self) -> bool {
+        extract::attr(&self.0, "disabled").is_none()
+    }
+
+    async fn click(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn clear(&self) {}
+
+    async fn send_keys(&self, _text: &str) -> Result<(), String> {
+        Ok(())
+    }
+}
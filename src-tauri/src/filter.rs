@@ -0,0 +1,198 @@
+// Per-query constraints: marketplace URL parameters and local post-filtering
+
+use crate::models::{Condition, Product, QueryFilter, QueryStatus, ShopProfile, ShopResults};
+use crate::parse::parse_price;
+
+impl QueryFilter {
+    pub fn is_empty(&self) -> bool {
+        self.min_price.is_none()
+            && self.max_price.is_none()
+            && self.include_words.is_empty()
+            && self.exclude_words.is_empty()
+            && self.min_rating.is_none()
+            && self.condition.is_none()
+    }
+
+    // Extra query string for https://www.tokopedia.com/search (starts with '&' when not empty).
+    // The marketplaces' rating filters rate products, not sellers, so `min_rating`
+    // is only checked against the shop profile (see `accepts_shop`).
+    pub fn tokopedia_params(&self) -> String {
+        let mut params = String::new();
+        if let Some(min) = self.min_price {
+            params.push_str(&format!("&pmin={}", min));
+        }
+        if let Some(max) = self.max_price {
+            params.push_str(&format!("&pmax={}", max));
+        }
+        match self.condition {
+            Some(Condition::New) => params.push_str("&condition=1"),
+            Some(Condition::Used) => params.push_str("&condition=2"),
+            None => {}
+        }
+        params
+    }
+
+    // Extra query string for https://shopee.co.id/search (starts with '&' when not empty)
+    pub fn shopee_params(&self) -> String {
+        let mut params = String::new();
+        if let Some(min) = self.min_price {
+            params.push_str(&format!("&minPrice={}", min));
+        }
+        if let Some(max) = self.max_price {
+            params.push_str(&format!("&maxPrice={}", max));
+        }
+        match self.condition {
+            Some(Condition::New) => params.push_str("&conditions=new"),
+            Some(Condition::Used) => params.push_str("&conditions=used"),
+            None => {}
+        }
+        params
+    }
+
+    // Post-filter for constraints that can be checked on a scraped product.
+    // Unknown values (e.g. an unparseable price) are let through.
+    pub fn matches(&self, product: &Product) -> bool {
+        if self.min_price.is_some() || self.max_price.is_some() {
+            if let Some(price) = parse_price(&product.price) {
+                if self.min_price.is_some_and(|min| price < min) {
+                    return false;
+                }
+                if self.max_price.is_some_and(|max| price > max) {
+                    return false;
+                }
+            }
+        }
+
+        // Condition is only known after a detail page visit (deep scrape). Without
+        // one the product passes: the search URL already asked for the condition
+        // where the platform supports it.
+        if let (Some(wanted), Some(actual)) = (
+            self.condition,
+            product.detail.as_ref().and_then(|d| d.condition),
+        ) {
+            if wanted != actual {
+                return false;
+            }
+        }
+
+        let name = product.name.to_lowercase();
+        if self
+            .include_words
+            .iter()
+            .filter(|w| !w.trim().is_empty())
+            .any(|w| !name.contains(&w.trim().to_lowercase()))
+        {
+            return false;
+        }
+        if self
+            .exclude_words
+            .iter()
+            .filter(|w| !w.trim().is_empty())
+            .any(|w| name.contains(&w.trim().to_lowercase()))
+        {
+            return false;
+        }
+
+        true
+    }
+
+    // Minimum seller rating, checked against the shop profile. A shop whose rating
+    // couldn't be read is let through, like other unknown values.
+    pub fn accepts_shop(&self, profile: Option<&ShopProfile>) -> bool {
+        match (self.min_rating, profile.and_then(|p| p.rating)) {
+            (Some(min), Some(rating)) => rating >= min,
+            _ => true,
+        }
+    }
+}
+
+// Empties the results of shops rated below the minimum seller rating of the result's
+// own query line. A failed search stays failed.
+pub(crate) fn drop_low_rated_shops(shops: &mut [ShopResults]) {
+    for shop in shops.iter_mut() {
+        for result in shop.results.iter_mut() {
+            if result.filter.accepts_shop(shop.profile.as_ref()) {
+                continue;
+            }
+            result.products.clear();
+            if result.status == QueryStatus::Ok {
+                result.status = QueryStatus::Empty;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ProductDetail, QueryResult, SearchQuery};
+
+    fn product(condition: Option<Condition>) -> Product {
+        Product {
+            name: "Bor Listrik Makita".to_string(),
+            price: "Rp350.000".to_string(),
+            // Product ratings don't count towards the seller rating
+            rating: Some(3.0),
+            detail: condition.map(|condition| ProductDetail {
+                condition: Some(condition),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn unknown_condition_passes() {
+        let filter = QueryFilter {
+            condition: Some(Condition::New),
+            min_rating: Some(4.5),
+            ..Default::default()
+        };
+        assert!(filter.matches(&product(None)));
+        assert!(filter.matches(&product(Some(Condition::New))));
+        assert!(!filter.matches(&product(Some(Condition::Used))));
+    }
+
+    #[test]
+    fn min_rating_is_the_seller_rating() {
+        let filter = QueryFilter {
+            min_rating: Some(4.5),
+            ..Default::default()
+        };
+        assert_eq!(filter.tokopedia_params(), "");
+        assert_eq!(filter.shopee_params(), "");
+
+        let rated = |rating| ShopProfile {
+            rating,
+            ..Default::default()
+        };
+        assert!(filter.accepts_shop(Some(&rated(Some(4.8)))));
+        assert!(!filter.accepts_shop(Some(&rated(Some(4.2)))));
+        assert!(filter.accepts_shop(Some(&rated(None))));
+        assert!(filter.accepts_shop(None));
+
+        // Same text on two list lines; only one of them asks for a well rated seller
+        let mut strict = SearchQuery::from("bor listrik");
+        strict.filter = filter;
+        let loose = SearchQuery::from("bor listrik");
+        let mut shops = vec![ShopResults {
+            shop_name: "Toko Perkakas".to_string(),
+            shop_url: "https://www.tokopedia.com/tokoperkakas".to_string(),
+            platform: "tokopedia".to_string(),
+            results: vec![
+                QueryResult::new(&strict, vec![product(None)]),
+                QueryResult::new(&loose, vec![product(None)]),
+                QueryResult::failed(&strict, "timeout".to_string()),
+            ],
+            profile: Some(rated(Some(4.2))),
+            basket: None,
+        }];
+        drop_low_rated_shops(&mut shops);
+
+        let results = &shops[0].results;
+        assert!(results[0].products.is_empty());
+        assert_eq!(results[0].status, QueryStatus::Empty);
+        assert_eq!(results[1].products.len(), 1);
+        assert_eq!(results[2].status, QueryStatus::Failed);
+    }
+}
@@ -0,0 +1,135 @@
+// Pages downloaded over plain HTTP instead of loaded in Chrome. Tokopedia renders its
+// search results and shop pages on the server (the `data-ssr` blocks the selectors
+// look for), so for those no browser is needed. A page without the expected content
+// is taken as blocked, e.g. a captcha or bot check, so the caller can use the browser.
+
+use log::info;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, ACCEPT_LANGUAGE};
+use reqwest::StatusCode;
+use std::fmt;
+use tokio::sync::Mutex;
+use tokio::time::{sleep, Duration, Instant};
+
+use crate::extract;
+use crate::models::ScrapeOptions;
+
+// Sent as a desktop Chrome; the default reqwest agent gets a bot check
+const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 \
+    (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36";
+
+// First pause before retrying a failed download; doubles with every attempt
+const FIRST_BACKOFF: Duration = Duration::from_secs(2);
+
+#[derive(Debug)]
+pub enum FetchError {
+    // The site refused the request or answered with something else than the page
+    Blocked(String),
+    // The page couldn't be downloaded, after retries
+    Failed(String),
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchError::Blocked(reason) => write!(f, "blocked: {}", reason),
+            FetchError::Failed(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+/// Downloads pages one after the other with `options.politeness_ms` in between.
+pub struct HttpFetcher {
+    client: reqwest::Client,
+    politeness: Duration,
+    retries: u32,
+    last_request: Mutex<Option<Instant>>,
+}
+
+impl HttpFetcher {
+    pub fn new(options: &ScrapeOptions) -> Result<Self, String> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            ACCEPT,
+            HeaderValue::from_static("text/html,application/xhtml+xml;q=0.9,*/*;q=0.8"),
+        );
+        headers.insert(ACCEPT_LANGUAGE, HeaderValue::from_static("id-ID,id;q=0.9,en;q=0.8"));
+        let client = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .default_headers(headers)
+            .gzip(true)
+            .timeout(Duration::from_secs(options.wait_timeout_secs.max(1) * 2))
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+        Ok(HttpFetcher {
+            client,
+            politeness: Duration::from_millis(options.politeness_ms),
+            retries: options.max_retries,
+            last_request: Mutex::new(None),
+        })
+    }
+
+    // Waits out the politeness delay since the previous request and claims the next slot
+    async fn wait_turn(&self) {
+        let mut last = self.last_request.lock().await;
+        if let Some(previous) = *last {
+            let ready = previous + self.politeness;
+            let now = Instant::now();
+            if ready > now {
+                sleep(ready - now).await;
+            }
+        }
+        *last = Some(Instant::now());
+    }
+
+    /// Downloads `url` and checks that one of the `ready` selectors is in it. Returns
+    /// the HTML and the index of the selector found, like `navigate::open_page`.
+    pub async fn page(&self, url: &str, ready: &[&str]) -> Result<(String, usize), FetchError> {
+        let mut backoff = FIRST_BACKOFF;
+        let mut attempt = 0;
+        loop {
+            let problem = match self.download(url).await {
+                Ok(html) => match extract::find_any(&html, ready) {
+                    Some(index) => return Ok((html, index)),
+                    // Same request, same answer: no point in retrying
+                    None => return Err(FetchError::Blocked(format!("{} has no expected content", url))),
+                },
+                Err(FetchError::Blocked(reason)) => return Err(FetchError::Blocked(reason)),
+                Err(FetchError::Failed(problem)) => problem,
+            };
+
+            if attempt >= self.retries {
+                return Err(FetchError::Failed(format!(
+                    "Failed to download {} after {} attempt(s): {}",
+                    url,
+                    attempt + 1,
+                    problem
+                )));
+            }
+            attempt += 1;
+            info!("Retrying {} in {:?}: {}", url, backoff, problem);
+            sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+
+    async fn download(&self, url: &str) -> Result<String, FetchError> {
+        self.wait_turn().await;
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| FetchError::Failed(e.to_string()))?;
+        let status = response.status();
+        if matches!(status, StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS) {
+            return Err(FetchError::Blocked(format!("{} answered {}", url, status)));
+        }
+        if !status.is_success() {
+            return Err(FetchError::Failed(format!("server answered {}", status)));
+        }
+        response
+            .text()
+            .await
+            .map_err(|e| FetchError::Failed(e.to_string()))
+    }
+}
@@ -0,0 +1,282 @@
+// Shopping list import from CSV / XLSX files
+
+use calamine::{open_workbook_auto_from_rs, Data, Reader};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Cursor;
+use std::path::PathBuf;
+
+use crate::models::{QueryFilter, SearchQuery};
+use crate::parse::parse_price;
+
+/// Which header holds which field. Stored in `<config dir>/satu-toko/import_mapping.json`
+/// so the next file with the same headers imports without asking again.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct ColumnMapping {
+    pub name: String,
+    pub quantity: Option<String>,
+    pub max_price: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct RowError {
+    // 1-based row number as shown in a spreadsheet (the header is row 1)
+    pub row: usize,
+    pub message: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ImportResult {
+    pub queries: Vec<SearchQuery>,
+    pub errors: Vec<RowError>,
+    // Mapping that was used, so the frontend can show and adjust it
+    pub mapping: ColumnMapping,
+    pub headers: Vec<String>,
+}
+
+fn mapping_file() -> Result<PathBuf, String> {
+    let config_dir = dirs::config_dir().ok_or("Could not determine config directory")?;
+    Ok(config_dir.join("satu-toko").join("import_mapping.json"))
+}
+
+pub fn load_mapping() -> Option<ColumnMapping> {
+    let content = fs::read_to_string(mapping_file().ok()?).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+pub fn save_mapping(mapping: &ColumnMapping) -> Result<(), String> {
+    let path = mapping_file()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(mapping)
+        .map_err(|e| format!("Failed to serialize column mapping: {}", e))?;
+    fs::write(path, content).map_err(|e| format!("Failed to write config: {}", e))
+}
+
+// Guess columns from common Indonesian / English header names
+fn detect_mapping(headers: &[String]) -> ColumnMapping {
+    let find = |candidates: &[&str]| {
+        headers
+            .iter()
+            .find(|h| {
+                let h = h.to_lowercase();
+                candidates.iter().any(|c| h.contains(c))
+            })
+            .cloned()
+    };
+
+    ColumnMapping {
+        name: find(&["nama", "item", "produk", "barang", "name", "product"])
+            .or_else(|| headers.first().cloned())
+            .unwrap_or_default(),
+        quantity: find(&["jumlah", "qty", "quantity", "kuantitas", "jml"]),
+        max_price: find(&["harga", "price", "budget", "anggaran"]),
+        notes: find(&["catatan", "keterangan", "note", "ket"]),
+    }
+}
+
+// Saved mapping only applies when this file actually has its name column
+fn resolve_mapping(headers: &[String], requested: Option<ColumnMapping>) -> ColumnMapping {
+    let has = |column: &str| headers.iter().any(|h| h == column);
+    match requested.or_else(load_mapping) {
+        Some(mapping) if has(&mapping.name) => ColumnMapping {
+            quantity: mapping.quantity.filter(|c| has(c)),
+            max_price: mapping.max_price.filter(|c| has(c)),
+            notes: mapping.notes.filter(|c| has(c)),
+            name: mapping.name,
+        },
+        _ => detect_mapping(headers),
+    }
+}
+
+fn cell_to_string(cell: &Data) -> String {
+    match cell {
+        Data::Empty => String::new(),
+        // Spreadsheet numbers come back as floats; keep whole numbers free of ".0"
+        Data::Float(f) if f.fract() == 0.0 => format!("{}", *f as i64),
+        other => other.to_string(),
+    }
+}
+
+fn read_rows(file_name: &str, content: &[u8]) -> Result<Vec<Vec<String>>, String> {
+    let lower = file_name.to_lowercase();
+    if lower.ends_with(".xlsx") || lower.ends_with(".xls") || lower.ends_with(".ods") {
+        let mut workbook = open_workbook_auto_from_rs(Cursor::new(content))
+            .map_err(|e| format!("Failed to open spreadsheet: {}", e))?;
+        let range = workbook
+            .worksheet_range_at(0)
+            .ok_or("Spreadsheet has no sheets")?
+            .map_err(|e| format!("Failed to read first sheet: {}", e))?;
+        Ok(range
+            .rows()
+            .map(|row| row.iter().map(cell_to_string).collect())
+            .collect())
+    } else if lower.ends_with(".csv") || lower.ends_with(".txt") {
+        // Excel in Indonesian locale saves CSV with ';'
+        let first_line = content.split(|b| *b == b'\n').next().unwrap_or(&[]);
+        let delimiter = if first_line.iter().filter(|b| **b == b';').count()
+            > first_line.iter().filter(|b| **b == b',').count()
+        {
+            b';'
+        } else {
+            b','
+        };
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .delimiter(delimiter)
+            .from_reader(content);
+        let mut rows = Vec::new();
+        for (index, record) in reader.records().enumerate() {
+            let record = record.map_err(|e| format!("Row {}: {}", index + 1, e))?;
+            rows.push(record.iter().map(|c| c.to_string()).collect());
+        }
+        Ok(rows)
+    } else {
+        Err(format!("Unsupported file type: {}", file_name))
+    }
+}
+
+// Whole number of items, with '.' allowed only as a thousands separator ("1.200");
+// "1.5" or "2,5" isn't a quantity
+fn parse_quantity(text: &str) -> Option<u32> {
+    let mut groups = text.split('.');
+    let first = groups.next()?;
+    let rest: Vec<&str> = groups.collect();
+    let is_digits = |g: &str| !g.is_empty() && g.bytes().all(|b| b.is_ascii_digit());
+    let grouped = rest.is_empty() || (first.len() <= 3 && rest.iter().all(|g| g.len() == 3));
+    if !is_digits(first) || !rest.iter().all(|g| is_digits(g)) || !grouped {
+        return None;
+    }
+    text.replace('.', "").parse().ok()
+}
+
+/// Parses a shopping list (header row + one item per row) into queries for `scrape_products`.
+/// Malformed rows are skipped and reported; the rest are still imported.
+pub fn parse_shopping_list(
+    file_name: &str,
+    content: &[u8],
+    mapping: Option<ColumnMapping>,
+) -> Result<ImportResult, String> {
+    let mut rows = read_rows(file_name, content)?.into_iter();
+    let headers: Vec<String> = rows
+        .next()
+        .ok_or("File is empty")?
+        .into_iter()
+        .map(|h| h.trim().to_string())
+        .collect();
+
+    let mapping = resolve_mapping(&headers, mapping);
+    let column = |name: &Option<String>| {
+        name.as_ref()
+            .and_then(|n| headers.iter().position(|h| h == n))
+    };
+    let name_col = headers
+        .iter()
+        .position(|h| *h == mapping.name)
+        .ok_or_else(|| format!("Column '{}' not found", mapping.name))?;
+    let quantity_col = column(&mapping.quantity);
+    let max_price_col = column(&mapping.max_price);
+    let notes_col = column(&mapping.notes);
+
+    let mut queries = Vec::new();
+    let mut errors = Vec::new();
+
+    for (index, row) in rows.enumerate() {
+        let row_number = index + 2;
+        let cell = |col: Option<usize>| {
+            col.and_then(|c| row.get(c))
+                .map(|v| v.trim().to_string())
+                .unwrap_or_default()
+        };
+
+        // Skip blank lines silently
+        if row.iter().all(|c| c.trim().is_empty()) {
+            continue;
+        }
+
+        let name = cell(Some(name_col));
+        if name.is_empty() {
+            errors.push(RowError {
+                row: row_number,
+                message: "Nama item kosong".to_string(),
+            });
+            continue;
+        }
+
+        let quantity_text = cell(quantity_col);
+        let quantity = if quantity_text.is_empty() {
+            1
+        } else {
+            match parse_quantity(&quantity_text) {
+                Some(q) if q > 0 => q,
+                _ => {
+                    errors.push(RowError {
+                        row: row_number,
+                        message: format!("Jumlah tidak valid: '{}'", quantity_text),
+                    });
+                    continue;
+                }
+            }
+        };
+
+        let max_price_text = cell(max_price_col);
+        let max_price = if max_price_text.is_empty() {
+            None
+        } else {
+            match parse_price(&max_price_text) {
+                Some(price) => Some(price),
+                None => {
+                    errors.push(RowError {
+                        row: row_number,
+                        message: format!("Harga maksimal tidak valid: '{}'", max_price_text),
+                    });
+                    continue;
+                }
+            }
+        };
+
+        let notes = cell(notes_col);
+        queries.push(SearchQuery {
+            text: name,
+            quantity,
+            filter: QueryFilter {
+                max_price,
+                ..Default::default()
+            },
+            notes: if notes.is_empty() { None } else { Some(notes) },
+        });
+    }
+
+    Ok(ImportResult {
+        queries,
+        errors,
+        mapping,
+        headers,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantities_are_whole_numbers() {
+        let csv = "Nama,Jumlah\nKabel NYM,1.200\nFitting lampu,1.5\nSaklar,\"2,5\"\nStop kontak,3\n";
+        let result = parse_shopping_list("daftar.csv", csv.as_bytes(), None).expect("imports");
+
+        let quantities: Vec<(&str, u32)> = result
+            .queries
+            .iter()
+            .map(|q| (q.text.as_str(), q.quantity))
+            .collect();
+        assert_eq!(quantities, [("Kabel NYM", 1200), ("Stop kontak", 3)]);
+        let rows: Vec<usize> = result.errors.iter().map(|e| e.row).collect();
+        assert_eq!(rows, [3, 4]);
+        assert_eq!(result.errors[0].message, "Jumlah tidak valid: '1.5'");
+    }
+}
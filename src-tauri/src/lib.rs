@@ -1,17 +1,45 @@
 // Main library file for SatuToko
 // This file contains Tauri commands and re-exports from other modules
 
+use tauri::{Emitter, Manager};
 use tauri_plugin_log::{Target, TargetKind};
 use std::fs;
 
-// Import modules
-mod chromedriver;
-mod models;
+// Import modules (public ones are also used by the satu-toko-cli binary and the tests)
+pub mod archive;
+mod basket;
+mod browser;
+pub mod chromedriver;
+pub mod coordinator;
+pub mod diagnose;
+pub mod export;
+pub mod extract;
+#[cfg(test)]
+mod fake_browser;
+mod filter;
+mod http_fetch;
+mod import;
+pub mod models;
+mod navigate;
+pub mod parse;
 mod platforms;
-mod scraper;
+pub mod progress;
+mod result_cache;
+pub mod scraper;
+pub mod selectors;
+mod session;
+mod shipping;
+mod shop_names;
+mod tabs;
+mod wait;
 
 // Re-export commonly used types
-pub use models::{Product, QueryResult, ShopResults};
+pub use models::{
+    BasketSummary, PlatformReport, PlatformStatus, Product, ProductDetail, QueryFilter,
+    QueryResult, QueryStatus, ScrapeOptions, ScrapeRun, SearchQuery, ShopProfile,
+    ShopResults,
+};
+pub use progress::{ProgressEvent, ProgressSink};
 
 #[tauri::command]
 fn greet(name: &str) -> String {
@@ -26,11 +54,40 @@ async fn ensure_chromedriver() -> Result<String, String> {
 #[tauri::command]
 async fn scrape_products(
     window: tauri::Window,
-    queries: Vec<String>,
+    queries: Vec<SearchQuery>,
     platform: String,
     limit: usize,
-) -> Result<Vec<ShopResults>, String> {
-    scraper::scrape_products(window, queries, platform, limit).await
+    options: Option<ScrapeOptions>,
+) -> Result<ScrapeRun, String> {
+    coordinator::scrape_products(queries, platform, limit, options.unwrap_or_default(), &window).await
+}
+
+#[tauri::command]
+async fn extend_scrape(
+    window: tauri::Window,
+    results: Vec<ShopResults>,
+    queries: Vec<SearchQuery>,
+    limit: usize,
+    options: Option<ScrapeOptions>,
+) -> Result<ScrapeRun, String> {
+    coordinator::extend_scrape(results, queries, limit, options.unwrap_or_default(), &window).await
+}
+
+// The app window is one progress sink: every event goes out as "scrape:event", and
+// finished shops / the end of the run keep their "scrape:progress" / "scrape:done" events.
+impl ProgressSink for tauri::Window {
+    fn emit(&self, event: ProgressEvent) {
+        match &event {
+            ProgressEvent::ShopFinished { shop } => {
+                let _ = Emitter::emit(self, "scrape:progress", shop);
+            }
+            ProgressEvent::Done => {
+                let _ = Emitter::emit(self, "scrape:done", ());
+            }
+            _ => {}
+        }
+        let _ = Emitter::emit(self, "scrape:event", &event);
+    }
 }
 
 #[tauri::command]
@@ -121,32 +178,46 @@ fn set_chrome_profile_path(path: String) -> Result<(), String> {
     Ok(())
 }
 
+#[tauri::command]
+async fn diagnose_platform(
+    app: tauri::AppHandle,
+    platform: String,
+) -> Result<diagnose::DiagnosticReport, String> {
+    let log_dir = app
+        .path()
+        .app_log_dir()
+        .map_err(|e| format!("Failed to find log directory: {}", e))?;
+    diagnose::diagnose_platform(&platform, &log_dir).await
+}
+
+#[tauri::command]
+fn get_shipping_config() -> shipping::ShippingConfig {
+    shipping::load_config()
+}
+
+#[tauri::command]
+fn set_shipping_config(config: shipping::ShippingConfig) -> Result<(), String> {
+    // Validate the rate table now rather than failing the next scrape
+    shipping::ShippingEstimator::from_config(config.clone())?;
+    shipping::save_config(&config)
+}
+
+#[tauri::command]
+fn import_shopping_list(
+    file_name: String,
+    content: Vec<u8>,
+    mapping: Option<import::ColumnMapping>,
+) -> Result<import::ImportResult, String> {
+    let result = import::parse_shopping_list(&file_name, &content, mapping)?;
+    // Remember the mapping for the next import
+    import::save_mapping(&result.mapping)?;
+    Ok(result)
+}
+
 #[tauri::command]
 async fn export_to_excel(results: Vec<ShopResults>) -> Result<String, String> {
     use chrono::Local;
     
-    // Create CSV content
-    let mut csv_content = String::from("Nama Toko,Platform,URL Toko,Query,Nama Produk,Harga,Lokasi,Link Produk\n");
-    
-    for shop in results {
-        for query_result in shop.results {
-            for product in query_result.products {
-                let row = format!(
-                    "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\"\n",
-                    shop.shop_name,
-                    if shop.platform == "tokopedia" { "Tokopedia" } else { "Shopee" },
-                    shop.shop_url,
-                    query_result.query,
-                    product.name,
-                    product.price,
-                    product.location,
-                    product.link
-                );
-                csv_content.push_str(&row);
-            }
-        }
-    }
-    
     // Get downloads directory
     let downloads_dir = dirs::download_dir()
         .ok_or("Could not determine downloads directory")?;
@@ -157,12 +228,167 @@ async fn export_to_excel(results: Vec<ShopResults>) -> Result<String, String> {
     let file_path = downloads_dir.join(&filename);
     
     // Write file
-    fs::write(&file_path, csv_content)
-        .map_err(|e| format!("Failed to write CSV file: {}", e))?;
+    export::write_csv(&results, &file_path)?;
     
     Ok(file_path.to_string_lossy().to_string())
 }
 
+// Current price, plus struck-through original price and discount when discounted
+fn price_cell(product: &Product) -> String {
+    match (&product.original_price, product.discount_percent) {
+        (Some(original), Some(discount)) => format!(
+            "{}<br><s>{}</s> -{}%",
+            product.price, original, discount
+        ),
+        (Some(original), None) => format!("{}<br><s>{}</s>", product.price, original),
+        _ => product.price.clone(),
+    }
+}
+
+// "★ 4.9 • 1000 terjual" plus badges
+fn stats_cell(product: &Product) -> String {
+    let mut parts = Vec::new();
+    if let Some(rating) = product.rating {
+        parts.push(format!("★ {}", rating));
+    }
+    if let Some(sold) = product.sold_count {
+        parts.push(format!("{} terjual", sold));
+    }
+    let mut cell = parts.join(" • ");
+    let badges = export::badge_labels(product);
+    if !badges.is_empty() {
+        if !cell.is_empty() {
+            cell.push_str("<br>");
+        }
+        cell.push_str(&badges);
+    }
+    cell
+}
+
+// One-line shop summary for the print header, e.g. "★ 4.9 (1200 ulasan) • Jakarta Barat • Official Store"
+fn profile_summary(profile: &ShopProfile) -> String {
+    let mut parts = Vec::new();
+    if let Some(rating) = profile.rating {
+        match profile.review_count {
+            Some(count) => parts.push(format!("★ {} ({} ulasan)", rating, count)),
+            None => parts.push(format!("★ {}", rating)),
+        }
+    }
+    if let Some(city) = &profile.city {
+        parts.push(city.clone());
+    }
+    if let Some(response_time) = &profile.response_time {
+        parts.push(response_time.clone());
+    }
+    if let Some(joined) = &profile.joined {
+        parts.push(format!("Bergabung {}", joined));
+    }
+    if profile.is_closed {
+        parts.push("Toko tutup".to_string());
+    }
+    parts.extend(profile.badges.iter().map(|b| b.label().to_string()));
+    parts.join(" • ")
+}
+
+// "Total: Rp150.000 (barang Rp130.000 + ongkir Rp20.000 via JNE REG, 1800 g)"
+fn basket_summary(basket: &BasketSummary) -> String {
+    let mut summary = match &basket.shipping {
+        Some(shipping) => format!(
+            "Total: {} (barang {} + ongkir {} via {}, {} g)",
+            format_rupiah(basket.total),
+            format_rupiah(basket.items_total),
+            format_rupiah(shipping.cost),
+            shipping.courier,
+            shipping.weight_grams
+        ),
+        None => format!("Total barang: {}", format_rupiah(basket.total)),
+    };
+    if !basket.missing_queries.is_empty() {
+        summary.push_str(&format!(" — tidak ada: {}", basket.missing_queries.join(", ")));
+    }
+    summary
+}
+
+// 1500000 -> "Rp1.500.000"
+fn format_rupiah(amount: u64) -> String {
+    let digits = amount.to_string();
+    let mut out = String::new();
+    for (i, ch) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push('.');
+        }
+        out.push(ch);
+    }
+    format!("Rp{}", out)
+}
+
+// Line totals of the items that make up a shop's basket
+fn basket_table(basket: &BasketSummary) -> String {
+    let mut table = String::from(r#"
+        <div class="query-section">
+            <div class="query-title">Keranjang</div>
+            <table class="product-table">
+                <thead>
+                    <tr>
+                        <th>Query</th>
+                        <th>Produk</th>
+                        <th>Harga Satuan</th>
+                        <th>Jumlah</th>
+                        <th>Subtotal</th>
+                    </tr>
+                </thead>
+                <tbody>
+"#);
+
+    for line in &basket.lines {
+        table.push_str(&format!(r#"
+                    <tr>
+                        <td>{}</td>
+                        <td class="product-name">{}{}</td>
+                        <td class="product-price">{}</td>
+                        <td>{}</td>
+                        <td class="product-price">{}</td>
+                    </tr>
+"#,
+            line.query,
+            line.product_name,
+            line.warning
+                .as_ref()
+                .map(|w| format!(r#"<br><span class="no-results">{}</span>"#, w))
+                .unwrap_or_default(),
+            format_rupiah(line.unit_price),
+            line.quantity,
+            format_rupiah(line.line_total)
+        ));
+    }
+
+    if let Some(shipping) = &basket.shipping {
+        table.push_str(&format!(r#"
+                    <tr>
+                        <td colspan="4">Ongkir ({}, {} g)</td>
+                        <td class="product-price">{}</td>
+                    </tr>
+"#,
+            shipping.courier,
+            shipping.weight_grams,
+            format_rupiah(shipping.cost)
+        ));
+    }
+
+    table.push_str(&format!(r#"
+                    <tr>
+                        <th colspan="4">Total</th>
+                        <th class="product-price">{}</th>
+                    </tr>
+                </tbody>
+            </table>
+        </div>
+"#,
+        format_rupiah(basket.total)
+    ));
+    table
+}
+
 #[tauri::command]
 async fn create_print_html(results: Vec<ShopResults>) -> Result<String, String> {
     use chrono::Local;
@@ -209,6 +435,11 @@ async fn create_print_html(results: Vec<ShopResults>) -> Result<String, String>
             color: #0078d4;
             word-break: break-all;
         }}
+        .shop-profile {{
+            font-size: 12px;
+            color: #666;
+            margin-top: 4px;
+        }}
         .query-section {{
             margin-bottom: 20px;
             padding-left: 15px;
@@ -272,18 +503,27 @@ async fn create_print_html(results: Vec<ShopResults>) -> Result<String, String>
         <div class="shop-header">
             <div class="shop-name">{} - {}</div>
             <div class="shop-url">{}</div>
+            <div class="shop-profile">{}</div>
+            <div class="shop-profile">{}</div>
         </div>
 "#, 
             shop.shop_name,
             if shop.platform == "tokopedia" { "Tokopedia" } else { "Shopee" },
-            shop.shop_url
+            shop.shop_url,
+            shop.profile.as_ref().map(profile_summary).unwrap_or_default(),
+            shop.basket.as_ref().map(basket_summary).unwrap_or_default()
         ));
 
+        if let Some(basket) = &shop.basket {
+            html.push_str(&basket_table(basket));
+        }
+
         for query_result in shop.results {
             html.push_str(&format!(r#"
         <div class="query-section">
-            <div class="query-title">Query: "{}" ({} produk)</div>
+            <div class="query-title">Query: {}x "{}" ({} produk)</div>
 "#, 
+                query_result.quantity,
                 query_result.query,
                 query_result.products.len()
             ));
@@ -296,6 +536,7 @@ async fn create_print_html(results: Vec<ShopResults>) -> Result<String, String>
                         <th>No</th>
                         <th>Nama Produk</th>
                         <th>Harga</th>
+                        <th>Rating / Terjual</th>
                         <th>Link</th>
                     </tr>
                 </thead>
@@ -308,12 +549,14 @@ async fn create_print_html(results: Vec<ShopResults>) -> Result<String, String>
                         <td>{}</td>
                         <td class="product-name">{}</td>
                         <td class="product-price">{}</td>
+                        <td>{}</td>
                         <td class="product-link">{}</td>
                     </tr>
 "#,
                         index + 1,
                         product.name,
-                        product.price,
+                        price_cell(product),
+                        stats_cell(product),
                         product.link
                     ));
                 }
@@ -396,11 +639,16 @@ pub fn run() {
             greet,
             ensure_chromedriver,
             scrape_products,
+            extend_scrape,
             get_chrome_and_driver_info,
             redownload_chromedriver,
             open_chrome_with_driver,
             get_chrome_profile_path,
             set_chrome_profile_path,
+            diagnose_platform,
+            get_shipping_config,
+            set_shipping_config,
+            import_shopping_list,
             export_to_excel,
             create_print_html,
             open_file_with_default_app
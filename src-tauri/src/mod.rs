@@ -1,9 +1,36 @@
 // Module organization for SatuToko
 
+pub mod archive;
+pub mod basket;
+pub mod browser;
 pub mod chromedriver;
+pub mod coordinator;
+pub mod diagnose;
+pub mod export;
+pub mod extract;
+#[cfg(test)]
+pub mod fake_browser;
+pub mod filter;
+pub mod http_fetch;
+pub mod import;
 pub mod models;
+pub mod navigate;
+pub mod parse;
 pub mod platforms;
+pub mod progress;
+pub mod result_cache;
 pub mod scraper;
+pub mod selectors;
+pub mod session;
+pub mod shipping;
+pub mod shop_names;
+pub mod tabs;
+pub mod wait;
 
 // Re-export commonly used types for convenience
-pub use models::{Product, QueryResult, ShopResults};
+pub use models::{
+    BasketSummary, PlatformReport, PlatformStatus, Product, ProductDetail, QueryFilter,
+    QueryResult, QueryStatus, ScrapeOptions, ScrapeRun, SearchQuery, ShopProfile,
+    ShopResults,
+};
+pub use progress::{ProgressEvent, ProgressSink};
@@ -1,6 +1,11 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
 
-#[derive(Serialize, Deserialize, Clone)]
+use crate::archive::SessionArchive;
+use crate::result_cache::ResultCache;
+
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct Product {
     pub name: String,
     pub price: String,
@@ -8,12 +13,227 @@ pub struct Product {
     pub location: String,
     pub photo: String,
     pub link: String,
+    // Optional card details, missing on older saved results
+    #[serde(default)]
+    pub rating: Option<f32>,
+    #[serde(default)]
+    pub sold_count: Option<u64>,
+    #[serde(default)]
+    pub discount_percent: Option<u8>,
+    #[serde(default)]
+    pub original_price: Option<String>,
+    #[serde(default)]
+    pub badges: Vec<Badge>,
+    // Only filled when the product detail page was visited
+    #[serde(default)]
+    pub detail: Option<ProductDetail>,
+}
+
+/// Data that is only available on the product detail page.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct ProductDetail {
+    pub stock: Option<u64>,
+    pub variants: Vec<Variant>,
+    pub weight_grams: Option<u32>,
+    pub condition: Option<Condition>,
+    pub min_order: Option<u32>,
+    pub description: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Variant {
+    pub name: String,
+    // Price shown after selecting the variant, when it differs per variant
+    pub price: Option<String>,
+}
+
+/// Options for a scrape run that aren't tied to a single query.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ScrapeOptions {
+    // Visit every matched product page for stock, variants, weight, etc.
+    pub deep_scrape: bool,
+    // Browser sessions running at the same time in "all" mode (one per platform)
+    pub max_sessions: usize,
+    // Tabs per session searching shops at the same time (Tokopedia)
+    pub max_tabs: usize,
+    // Minimum delay between two page loads of the tab pool, across its tabs
+    pub politeness_ms: u64,
+    // Longest wait for a page or element before going on without it
+    pub wait_timeout_secs: u64,
+    // How long a page must stay unchanged (requests, card count) to count as loaded
+    pub settle_ms: u64,
+    // Extra attempts for a page that fails to load, with doubling pauses in between
+    pub max_retries: u32,
+    // Folder to record every visited page and the results into, for a later replay
+    pub record_dir: Option<String>,
+    // How each platform's search and shop pages are loaded; missing means the browser
+    pub fetch_modes: BTreeMap<String, FetchMode>,
+    // How long a shop's results for a query are reused by later runs; 0 turns the
+    // result cache off
+    pub cache_ttl_secs: u64,
+    // Search everything again, still refreshing the cache
+    pub refresh_cache: bool,
+    // Which queries' search results the shops are discovered from
+    pub seed: SeedMode,
+    // Set by the coordinator while recording or replaying a session
+    #[serde(skip)]
+    pub archive: Option<Arc<SessionArchive>>,
+    // Set by the coordinator when results are cached
+    #[serde(skip)]
+    pub cache: Option<Arc<ResultCache>>,
+}
+
+impl Default for ScrapeOptions {
+    fn default() -> Self {
+        ScrapeOptions {
+            deep_scrape: false,
+            max_sessions: 2,
+            max_tabs: 3,
+            politeness_ms: 500,
+            wait_timeout_secs: 10,
+            settle_ms: 700,
+            max_retries: 2,
+            record_dir: None,
+            fetch_modes: BTreeMap::new(),
+            cache_ttl_secs: 6 * 60 * 60,
+            refresh_cache: false,
+            seed: SeedMode::default(),
+            archive: None,
+            cache: None,
+        }
+    }
+}
+
+impl ScrapeOptions {
+    pub fn fetch_mode(&self, platform: &str) -> FetchMode {
+        self.fetch_modes.get(platform).copied().unwrap_or_default()
+    }
+}
+
+/// How a platform's pages are loaded.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FetchMode {
+    #[default]
+    Browser,
+    // Server-rendered HTML downloaded without a browser (Tokopedia only); falls back
+    // to the browser when the site blocks it
+    Http,
+}
+
+/// The "seed" queries whose marketplace-wide search results the shops are discovered
+/// from; every other query is only searched inside those shops.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum SeedMode {
+    // The first query of the list
+    #[default]
+    First,
+    // The query at `index` in the list
+    Query { index: usize },
+    // The query with the fewest results, after loading the first search page of each
+    Rarest,
+    // Shops from the top `limit` results of each of these queries
+    Union { indices: Vec<usize> },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Badge {
+    OfficialStore,
+    PowerMerchant,
+    StarSeller,
+    Mall,
+}
+
+impl Badge {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Badge::OfficialStore => "Official Store",
+            Badge::PowerMerchant => "Power Merchant",
+            Badge::StarSeller => "Star Seller",
+            Badge::Mall => "Mall",
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct QueryResult {
     pub query: String,
     pub products: Vec<Product>,
+    // Desired quantity for this query, copied from the SearchQuery
+    #[serde(default = "default_quantity")]
+    pub quantity: u32,
+    // Constraints of the list line, copied from the SearchQuery: lines with the same
+    // text can still ask for different sellers
+    #[serde(default)]
+    pub filter: QueryFilter,
+    // Whether `products` can be trusted; older saved results count as `Ok`
+    #[serde(default)]
+    pub status: QueryStatus,
+    // Why the search failed, for `QueryStatus::Failed`
+    #[serde(default)]
+    pub error: Option<String>,
+    // Seconds since the shop was searched, when the products came from the result cache
+    #[serde(default)]
+    pub cached_age_secs: Option<u64>,
+}
+
+impl QueryResult {
+    /// Products from a page that loaded; no products means the shop has none.
+    pub fn new(query: &SearchQuery, products: Vec<Product>) -> Self {
+        QueryResult {
+            query: query.text.clone(),
+            status: if products.is_empty() {
+                QueryStatus::Empty
+            } else {
+                QueryStatus::Ok
+            },
+            products,
+            quantity: query.quantity,
+            filter: query.filter.clone(),
+            error: None,
+            cached_age_secs: None,
+        }
+    }
+
+    /// Products of an earlier run's search, `age_secs` old.
+    pub fn cached(query: &SearchQuery, products: Vec<Product>, age_secs: u64) -> Self {
+        QueryResult {
+            cached_age_secs: Some(age_secs),
+            ..QueryResult::new(query, products)
+        }
+    }
+
+    /// The search page never loaded, so the shop may still sell this.
+    pub fn failed(query: &SearchQuery, error: String) -> Self {
+        QueryResult {
+            query: query.text.clone(),
+            products: Vec::new(),
+            quantity: query.quantity,
+            filter: query.filter.clone(),
+            status: QueryStatus::Failed,
+            error: Some(error),
+            cached_age_secs: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryStatus {
+    #[default]
+    Ok,
+    /// The page loaded and showed no (matching) products.
+    Empty,
+    /// Navigation failed or the page stayed broken after retries.
+    Failed,
+}
+
+fn default_quantity() -> u32 {
+    1
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -22,4 +242,164 @@ pub struct ShopResults {
     pub shop_url: String,
     pub platform: String,
     pub results: Vec<QueryResult>,
+    #[serde(default)]
+    pub profile: Option<ShopProfile>,
+    #[serde(default)]
+    pub basket: Option<BasketSummary>,
+}
+
+/// How one platform's part of a run ended.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum PlatformStatus {
+    Ok,
+    /// Failed midway; shops finished before the failure are kept.
+    Partial { reason: String },
+    Failed { reason: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PlatformReport {
+    pub platform: String,
+    #[serde(flatten)]
+    pub status: PlatformStatus,
+    pub shops: usize,
+}
+
+/// Result of `scrape_products`: shops from every platform plus how each platform fared.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ScrapeRun {
+    pub results: Vec<ShopResults>,
+    pub platforms: Vec<PlatformReport>,
+}
+
+/// Cost of buying the whole list from one shop: the cheapest match per query plus ongkir.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct BasketSummary {
+    pub lines: Vec<BasketLine>,
+    pub items_total: u64,
+    // Queries without any usable product in this shop
+    pub missing_queries: Vec<String>,
+    pub shipping: Option<ShippingEstimate>,
+    pub total: u64,
+}
+
+/// The product picked for one query and what it costs at the desired quantity.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BasketLine {
+    pub query: String,
+    pub product_name: String,
+    pub product_link: String,
+    pub unit_price: u64,
+    // Desired quantity raised to the product's minimum order when needed
+    pub quantity: u32,
+    pub line_total: u64,
+    // Why this line may not be fillable as requested, e.g. "stok 3 < 10"
+    pub warning: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ShippingEstimate {
+    pub courier: String,
+    pub origin: String,
+    pub destination: String,
+    pub weight_grams: u64,
+    pub cost: u64,
+    pub etd: Option<String>,
+}
+
+/// Shop-level details read from the shop page header, used to judge a shop
+/// before buying a whole basket from it.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct ShopProfile {
+    pub rating: Option<f32>,
+    pub review_count: Option<u64>,
+    pub response_time: Option<String>,
+    pub joined: Option<String>,
+    pub city: Option<String>,
+    // Official Store / Power Merchant / Star Seller / Mall
+    pub badges: Vec<Badge>,
+    pub online_status: Option<String>,
+    pub is_closed: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Condition {
+    New,
+    Used,
+}
+
+/// Optional constraints attached to a single query. Empty fields mean "no constraint".
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+#[serde(default)]
+pub struct QueryFilter {
+    pub min_price: Option<u64>,
+    pub max_price: Option<u64>,
+    pub include_words: Vec<String>,
+    pub exclude_words: Vec<String>,
+    pub min_rating: Option<f32>,
+    pub condition: Option<Condition>,
+}
+
+/// One item of the shopping list sent by the frontend.
+/// Accepts either a plain string (old history entries, "10x kabel NYM") or
+/// `{ text, quantity, filter }`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(from = "SearchQueryInput")]
+pub struct SearchQuery {
+    pub text: String,
+    pub quantity: u32,
+    pub filter: QueryFilter,
+    // Free text from imported shopping lists; not used for searching
+    pub notes: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SearchQueryInput {
+    Text(String),
+    Full {
+        text: String,
+        #[serde(default = "default_quantity")]
+        quantity: u32,
+        #[serde(default)]
+        filter: QueryFilter,
+        #[serde(default)]
+        notes: Option<String>,
+    },
+}
+
+// Plain text as typed on the command line, e.g. "10x kabel NYM"
+impl From<&str> for SearchQuery {
+    fn from(text: &str) -> Self {
+        SearchQueryInput::Text(text.to_string()).into()
+    }
+}
+
+impl From<SearchQueryInput> for SearchQuery {
+    fn from(input: SearchQueryInput) -> Self {
+        match input {
+            SearchQueryInput::Text(text) => {
+                let (quantity, text) = crate::parse::parse_quantity_prefix(&text);
+                SearchQuery {
+                    text,
+                    quantity,
+                    filter: QueryFilter::default(),
+                    notes: None,
+                }
+            }
+            SearchQueryInput::Full {
+                text,
+                quantity,
+                filter,
+                notes,
+            } => SearchQuery {
+                text,
+                quantity: quantity.max(1),
+                filter,
+                notes,
+            },
+        }
+    }
 }
@@ -0,0 +1,94 @@
+// Page loads that survive flaky connections: retried with doubling pauses, and
+// error or blank pages told apart from pages that merely have no results.
+
+use log::info;
+use tokio::time::{sleep, Duration};
+
+use crate::wait::{any_selector, Page, Waits};
+
+// Pause before the first retry; doubled for every further one
+const FIRST_BACKOFF: Duration = Duration::from_secs(2);
+
+// Why the current page is unusable, or null when it looks fine. Blank pages are
+// only reported with `arguments[0]`: an app shell is blank while it is loading.
+pub(crate) const PAGE_PROBLEM: &str = r#"
+if (window.__satuTokoLeaving) return "page did not load";
+if (location.href.startsWith("chrome-error://")) return "browser error page";
+const title = (document.title || "").toLowerCase();
+const text = ((document.body && document.body.innerText) || "").trim();
+const head = text.slice(0, 500).toLowerCase();
+const markers = [
+    "500 internal server error",
+    "502 bad gateway",
+    "503 service",
+    "504 gateway",
+    "access denied",
+    "too many requests",
+];
+for (const marker of markers) {
+    if (title.includes(marker) || head.includes(marker)) return marker;
+}
+if (arguments[0] && text.length === 0) return "blank page";
+return null;
+"#;
+
+/// Opens `url` and waits for one of `ready` (the page's content, or its "no results"
+/// state). A failed navigation, an error page or a page that never shows any of
+/// `ready` is tried again up to `waits.retries` times. Returns the index of the
+/// selector found.
+///
+/// When recording a session the page being left is saved first; when replaying one
+/// the recorded page is opened instead of `url`.
+pub(crate) async fn open_page<P: Page>(
+    page: &P,
+    url: &str,
+    ready: &[&str],
+    waits: &Waits,
+) -> Result<usize, String> {
+    let target = match &waits.archive {
+        Some(archive) => {
+            archive.leave(page).await;
+            archive.page_url(url)
+        }
+        None => url.to_string(),
+    };
+
+    let mut backoff = FIRST_BACKOFF;
+    let mut attempt = 0;
+    loop {
+        let problem = match page.navigate(&target).await {
+            Err(e) => e,
+            Ok(()) => match any_selector(page, ready, waits.timeout).await {
+                Some(index) => match page.problem(false).await {
+                    None => {
+                        if let Some(archive) = &waits.archive {
+                            archive.arrived(page, url).await;
+                        }
+                        return Ok(index);
+                    }
+                    Some(problem) => problem,
+                },
+                None => page.problem(true)
+                    .await
+                    .unwrap_or_else(|| "expected content did not appear".to_string()),
+            },
+        };
+
+        if attempt >= waits.retries {
+            // A recorded failure should fail the same way on replay
+            if let Some(archive) = &waits.archive {
+                archive.arrived(page, url).await;
+            }
+            return Err(format!(
+                "Failed to load {} after {} attempt(s): {}",
+                url,
+                attempt + 1,
+                problem
+            ));
+        }
+        attempt += 1;
+        info!("Retrying {} in {:?}: {}", url, backoff, problem);
+        sleep(backoff).await;
+        backoff *= 2;
+    }
+}
@@ -0,0 +1,395 @@
+// Helpers to turn the text shown on marketplace cards into typed values
+
+use crate::models::{Badge, Condition, Product, ProductDetail, ShopProfile};
+
+// Parse a displayed price such as "Rp12.500" or "Rp10.000 - Rp25.000" into rupiah.
+// For ranges the lower bound is used.
+pub fn parse_price(text: &str) -> Option<u64> {
+    let mut digits = String::new();
+    for ch in text.chars() {
+        if ch.is_ascii_digit() {
+            digits.push(ch);
+        } else if ch == '.' && !digits.is_empty() {
+            // thousands separator
+            continue;
+        } else if !digits.is_empty() {
+            break;
+        }
+    }
+    digits.parse().ok()
+}
+
+// "4.9" / "4,9" -> 4.9 (only values in the 0..=5 star range are accepted)
+pub fn parse_rating(text: &str) -> Option<f32> {
+    let value: f32 = text.trim().replace(',', ".").parse().ok()?;
+    if (0.0..=5.0).contains(&value) {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+// "100+ terjual", "1rb+ terjual", "2,5RB terjual", "1jt+ terjual" -> approximate count
+pub fn parse_sold_count(text: &str) -> Option<u64> {
+    let lower = text.to_lowercase();
+    // Shopee sometimes puts the label first: "Terjual 1rb+"
+    let before = match lower.split_once("terjual") {
+        Some((before, after)) if before.trim().is_empty() => after,
+        Some((before, _)) => before,
+        None => return None,
+    };
+    parse_count(before)
+}
+
+// Compact counts as shown by both marketplaces: "100+", "1.234", "2,5rb", "12,3RB", "1jt"
+pub fn parse_count(text: &str) -> Option<u64> {
+    let lower = text.trim().to_lowercase();
+    let trimmed = lower.trim_end_matches('+').trim();
+
+    let (number, multiplier) = if let Some(n) = trimmed.strip_suffix("rb") {
+        (n, 1_000.0)
+    } else if let Some(n) = trimmed.strip_suffix("jt") {
+        (n, 1_000_000.0)
+    } else {
+        (trimmed, 1.0)
+    };
+
+    let number = number.trim().trim_end_matches('+').replace(',', ".");
+    let number = number.rsplit(' ').next().unwrap_or("");
+    // Plain counts may use '.' as a thousands separator ("1.234")
+    let value: f64 = if multiplier == 1.0 {
+        number.replace('.', "").parse().ok()?
+    } else {
+        number.parse().ok()?
+    };
+    Some((value * multiplier).round() as u64)
+}
+
+// Result total of a Tokopedia search page: "Menampilkan 1 - 60 barang dari total 12,3rb
+// untuk "kabel"" -> 12300
+pub fn parse_result_total(text: &str) -> Option<u64> {
+    let lower = text.to_lowercase();
+    let (_, after) = lower.split_once("dari total")?;
+    parse_count(after.split_whitespace().next()?)
+}
+
+// "-25%", "25%", "25% off" -> 25
+pub fn parse_discount(text: &str) -> Option<u8> {
+    let trimmed = text.trim();
+    let (number, _) = trimmed.split_once('%')?;
+    let value: u8 = number.trim().trim_start_matches('-').trim().parse().ok()?;
+    if value > 0 && value < 100 {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+// Match a badge label or image alt text against the badges we care about
+pub fn parse_badge(text: &str) -> Option<Badge> {
+    let lower = text.trim().to_lowercase();
+    if lower.is_empty() {
+        return None;
+    }
+    if lower == "mall" || lower.contains("shopee mall") {
+        Some(Badge::Mall)
+    } else if lower.contains("official") {
+        Some(Badge::OfficialStore)
+    } else if lower.contains("power merchant") || lower == "pm" || lower == "pm pro" {
+        Some(Badge::PowerMerchant)
+    } else if lower == "star" || lower == "star+" || lower.contains("star seller") {
+        Some(Badge::StarSeller)
+    } else {
+        None
+    }
+}
+
+/// Values read from the full text of a search card, next to the fields we
+/// extract through dedicated selectors.
+#[derive(Default)]
+pub struct CardStats {
+    pub rating: Option<f32>,
+    pub sold_count: Option<u64>,
+    pub discount_percent: Option<u8>,
+    pub original_price: Option<String>,
+    pub badges: Vec<Badge>,
+}
+
+impl CardStats {
+    pub fn apply(self, product: &mut Product) {
+        product.rating = self.rating;
+        product.sold_count = self.sold_count;
+        product.discount_percent = self.discount_percent;
+        product.original_price = self.original_price;
+        product.badges = self.badges;
+    }
+}
+
+// Card text is one value per line; Tokopedia also joins rating and sold count with "•".
+// `badge_labels` are image alt/title attributes found in the card.
+pub fn parse_card_stats(card_text: &str, price: &str, badge_labels: &[String]) -> CardStats {
+    let mut stats = CardStats::default();
+    let current_price = parse_price(price);
+
+    let segments = card_text
+        .lines()
+        .flat_map(|line| line.split('•'))
+        .map(|segment| segment.trim())
+        .filter(|segment| !segment.is_empty());
+
+    for segment in segments {
+        let lower = segment.to_lowercase();
+        if lower.contains("terjual") {
+            if stats.sold_count.is_none() {
+                stats.sold_count = parse_sold_count(segment);
+            }
+        } else if segment.ends_with('%') || lower.ends_with("% off") {
+            if stats.discount_percent.is_none() {
+                stats.discount_percent = parse_discount(segment);
+            }
+        } else if lower.starts_with("rp") {
+            // A second, higher price on the card is the struck-through original price
+            if let (Some(current), Some(value)) = (current_price, parse_price(segment)) {
+                if value > current && stats.original_price.is_none() {
+                    stats.original_price = Some(segment.to_string());
+                }
+            }
+        } else if segment.len() <= 3 && segment.contains(['.', ',']) {
+            if stats.rating.is_none() {
+                stats.rating = parse_rating(segment);
+            }
+        } else if segment.len() <= 20 {
+            // Short labels only, so product names mentioning "official" don't count
+            if let Some(badge) = parse_badge(segment) {
+                if !stats.badges.contains(&badge) {
+                    stats.badges.push(badge);
+                }
+            }
+        }
+    }
+
+    for label in badge_labels {
+        if let Some(badge) = parse_badge(label) {
+            if !stats.badges.contains(&badge) {
+                stats.badges.push(badge);
+            }
+        }
+    }
+
+    stats
+}
+
+// Shop headers are parsed line by line; both marketplaces use "Label: value" or
+// "value label" lines, e.g. "Penilaian: 4.9 (12,3RB Penilaian)" on Shopee and
+// "4.9 rating & ulasan" / "± 1 jam pesanan diproses" on Tokopedia.
+pub fn parse_shop_profile(header_text: &str, badge_labels: &[String]) -> ShopProfile {
+    let mut profile = ShopProfile::default();
+
+    for line in header_text.lines().map(|l| l.trim()).filter(|l| !l.is_empty()) {
+        let lower = line.to_lowercase();
+        let value = line
+            .split_once(':')
+            .map(|(_, v)| v.trim())
+            .unwrap_or(line)
+            .to_string();
+
+        if lower.contains("penilaian") || lower.contains("rating") || lower.contains("ulasan") {
+            if profile.rating.is_none() {
+                profile.rating = value
+                    .split_whitespace()
+                    .find_map(|token| parse_rating(token.trim_matches(|c| c == '(' || c == ')')));
+            }
+            if profile.review_count.is_none() {
+                // The count is in parentheses on Shopee and Tokopedia alike
+                if let Some((_, inner)) = value.split_once('(') {
+                    let inner = inner.split(')').next().unwrap_or("");
+                    let number = inner
+                        .to_lowercase()
+                        .replace("penilaian", "")
+                        .replace("rating", "")
+                        .replace("ulasan", "");
+                    profile.review_count = parse_count(&number);
+                }
+            }
+        } else if lower.contains("bergabung") || lower.contains("buka sejak") {
+            if profile.joined.is_none() {
+                let joined = value.to_lowercase().replace("buka sejak", "");
+                profile.joined = Some(joined.trim().to_string()).filter(|v| !v.is_empty());
+            }
+        } else if lower.contains("diproses") || lower.contains("performa chat") || lower.contains("balas") {
+            if profile.response_time.is_none() {
+                profile.response_time = Some(value);
+            }
+        } else if lower.contains("tutup") || lower.contains("libur") {
+            profile.is_closed = true;
+        } else if lower.starts_with("online") || lower.starts_with("aktif") {
+            if profile.online_status.is_none() {
+                profile.online_status = Some(line.to_string());
+            }
+        } else if lower.starts_with("lokasi") || lower.starts_with("kota") {
+            if profile.city.is_none() {
+                profile.city = Some(value);
+            }
+        } else if line.len() <= 20 {
+            if let Some(badge) = parse_badge(line) {
+                if !profile.badges.contains(&badge) {
+                    profile.badges.push(badge);
+                }
+            }
+        }
+    }
+
+    for label in badge_labels {
+        if let Some(badge) = parse_badge(label) {
+            if !profile.badges.contains(&badge) {
+                profile.badges.push(badge);
+            }
+        }
+    }
+
+    profile
+}
+
+// "Berat Satuan: 1,5 kg", "Berat: 250 g", "Berat 250 gram" -> grams.
+// Looks at the first line mentioning "berat" and, if the value is on the next line, that one too.
+pub fn parse_weight(page_text: &str) -> Option<u32> {
+    let lines: Vec<&str> = page_text.lines().map(|l| l.trim()).collect();
+    for (index, line) in lines.iter().enumerate() {
+        if !line.to_lowercase().starts_with("berat") {
+            continue;
+        }
+        let value = line.split_once(':').map(|(_, v)| v).unwrap_or(line);
+        if let Some(grams) = weight_value(value) {
+            return Some(grams);
+        }
+        if let Some(next) = lines.get(index + 1) {
+            if let Some(grams) = weight_value(next) {
+                return Some(grams);
+            }
+        }
+    }
+    None
+}
+
+fn weight_value(text: &str) -> Option<u32> {
+    let lower = text.to_lowercase();
+    let start = lower.find(|c: char| c.is_ascii_digit())?;
+    let rest = &lower[start..];
+    let end = rest
+        .find(|c: char| !(c.is_ascii_digit() || c == ',' || c == '.'))
+        .unwrap_or(rest.len());
+    let number: f64 = rest[..end].replace(',', ".").parse().ok()?;
+    let unit = rest[end..].trim_start();
+    let grams = if unit.starts_with("kg") || unit.starts_with("kilo") {
+        number * 1000.0
+    } else if unit.starts_with('g') {
+        number
+    } else {
+        return None;
+    };
+    Some(grams.round() as u32)
+}
+
+// Detail page facts that both marketplaces show as "Label: value" or "Label value" lines:
+// "Kondisi: Baru", "Min. Pemesanan: 2 Buah", "Berat Satuan: 1 kg". Stock, variants and
+// description need selectors and are filled by the platform scrapers: descriptions and
+// reviews say "stok tersisa sedikit" too.
+pub fn parse_product_detail(page_text: &str) -> ProductDetail {
+    let mut detail = ProductDetail {
+        weight_grams: parse_weight(page_text),
+        ..Default::default()
+    };
+
+    let lines: Vec<&str> = page_text.lines().map(|l| l.trim()).filter(|l| !l.is_empty()).collect();
+    for (index, line) in lines.iter().enumerate() {
+        let lower = line.to_lowercase();
+        // Label and value can be on separate lines
+        let value = match line.split_once(':') {
+            Some((_, v)) if !v.trim().is_empty() => v.trim().to_string(),
+            _ => lines.get(index + 1).map(|v| format!("{} {}", line, v)).unwrap_or_default(),
+        };
+
+        if detail.condition.is_none() && lower.starts_with("kondisi") {
+            let value = value.to_lowercase();
+            if value.contains("baru") {
+                detail.condition = Some(Condition::New);
+            } else if value.contains("bekas") {
+                detail.condition = Some(Condition::Used);
+            }
+        } else if detail.min_order.is_none()
+            && (lower.starts_with("min. pemesanan") || lower.starts_with("minimal pembelian") || lower.starts_with("min. beli"))
+        {
+            detail.min_order = first_number(&value).and_then(|n| u32::try_from(n).ok());
+        }
+    }
+
+    detail
+}
+
+// Text of the stock label: "Stok: 25", "tersisa 120 buah", "Stok habis"
+pub fn parse_stock(label: &str) -> Option<u64> {
+    if label.to_lowercase().contains("habis") {
+        return Some(0);
+    }
+    first_number(label)
+}
+
+// First whole number in a string, allowing "." thousands separators: "Stok 1.234 buah" -> 1234
+fn first_number(text: &str) -> Option<u64> {
+    let start = text.find(|c: char| c.is_ascii_digit())?;
+    let rest = &text[start..];
+    let end = rest
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(rest.len());
+    rest[..end].replace('.', "").parse().ok()
+}
+
+// Units a size is given in: "100 x 200 mm plat" is a size, not 100 of "200 mm plat"
+const SIZE_UNITS: &[&str] = &["mm", "cm", "m", "meter", "inch", "in", "kg", "g", "gr", "ml", "l", "liter"];
+
+// "10x kabel NYM 2x1.5" -> (10, "kabel NYM 2x1.5"); "3 x MCB 16A" -> (3, "MCB 16A").
+// The "x" must be followed by a space so sizes like "2x1.5" aren't read as quantities,
+// and the name can't start with a number or a unit, so "100 x 200 mm plat" stays whole.
+pub fn parse_quantity_prefix(text: &str) -> (u32, String) {
+    let trimmed = text.trim();
+    let digits_end = trimmed
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(trimmed.len());
+    if digits_end > 0 {
+        let rest = trimmed[digits_end..].trim_start();
+        if let Some(name) = rest.strip_prefix(['x', 'X']) {
+            let name = name.trim();
+            let first_word = name
+                .split(|c: char| !c.is_alphanumeric())
+                .next()
+                .unwrap_or("")
+                .to_lowercase();
+            let is_size = name.starts_with(|c: char| c.is_ascii_digit())
+                || SIZE_UNITS.contains(&first_word.as_str());
+            if rest[1..].starts_with(' ') && !name.is_empty() && !is_size {
+                if let Ok(quantity) = trimmed[..digits_end].parse::<u32>() {
+                    if quantity > 0 {
+                        return (quantity, name.to_string());
+                    }
+                }
+            }
+        }
+    }
+    (1, trimmed.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantity_prefix_leaves_sizes_alone() {
+        assert_eq!(parse_quantity_prefix("10x kabel NYM 2x1.5"), (10, "kabel NYM 2x1.5".to_string()));
+        assert_eq!(parse_quantity_prefix("3 x MCB 16A"), (3, "MCB 16A".to_string()));
+        assert_eq!(parse_quantity_prefix("2x1.5 kabel"), (1, "2x1.5 kabel".to_string()));
+        assert_eq!(parse_quantity_prefix("100 x 200 mm plat"), (1, "100 x 200 mm plat".to_string()));
+        assert_eq!(parse_quantity_prefix("40 x 40 cm keramik"), (1, "40 x 40 cm keramik".to_string()));
+        assert_eq!(parse_quantity_prefix("5 x mm plat"), (1, "5 x mm plat".to_string()));
+        assert_eq!(parse_quantity_prefix("0x lampu"), (1, "0x lampu".to_string()));
+    }
+}
@@ -1,983 +1,1585 @@
+use futures::stream::{self, StreamExt};
 use log::info;
 use std::collections::{HashMap, HashSet};
-use tauri::Emitter;
+use std::fmt;
 use thirtyfour::prelude::*;
-use tokio::time::{sleep, Duration};
+use tokio::time::Duration;
+
+use crate::browser::{Browser, Element, Locator};
+use crate::models::{
+    Product, ProductDetail, QueryResult, ScrapeOptions, SearchQuery, SeedMode, ShopProfile,
+    ShopResults, Variant,
+};
+use crate::extract::{self, ResultCount};
+use crate::http_fetch::{FetchError, HttpFetcher};
+use crate::parse::{parse_product_detail, parse_shop_profile, parse_stock};
+use crate::progress::{page_loaded, warning, ProgressEvent, ProgressSink, ProgressTracker};
+use crate::result_cache::{normalize_query, ResultCache};
+use crate::selectors::Selectors;
+use crate::shop_names::ShopNames;
+use crate::tabs::{Tab, TabPool};
+use crate::navigate::open_page;
+use crate::wait::{any_selector, network_idle, stable_count, Waits};
+
+// Alt texts of images inside an element; badges are usually icons
+async fn image_labels<E: Element>(element: &E, sel: &Selectors) -> Vec<String> {
+    let mut labels = Vec::new();
+    for img in sel.find_all(element, "badge_images").await {
+        if let Some(alt) = img.attr("alt").await {
+            labels.push(alt);
+        }
+    }
+    labels
+}
+
+// Reads a card in one WebDriver call and parses its HTML with `extract`
+async fn read_card<E: Element>(card: &E, sel: &Selectors) -> Option<Product> {
+    let html = card.outer_html().await;
+    extract::card_from_html(&html, sel)
+}
+
+// The card location is the shop's city, so use the most common one when the
+// shop header didn't show it.
+fn fill_city_from_products(profile: &mut ShopProfile, results: &[QueryResult]) {
+    if profile.city.is_some() {
+        return;
+    }
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for product in results.iter().flat_map(|r| r.products.iter()) {
+        if !product.location.is_empty() {
+            *counts.entry(product.location.as_str()).or_insert(0) += 1;
+        }
+    }
+    profile.city = counts
+        .into_iter()
+        .max_by_key(|(_, count)| *count)
+        .map(|(city, _)| city.to_string());
+}
+
+// Queries a shop of an earlier run has no results for yet, case and spacing ignored
+fn missing_queries<'q>(shop: &ShopResults, queries: &'q [SearchQuery]) -> Vec<&'q SearchQuery> {
+    let mut searched: HashSet<String> = shop
+        .results
+        .iter()
+        .map(|r| normalize_query(&r.query))
+        .collect();
+    // A query listed twice is searched once
+    queries
+        .iter()
+        .filter(|q| searched.insert(normalize_query(&q.text)))
+        .collect()
+}
+
+// Upper bound on variant buttons clicked per product page
+const MAX_VARIANTS: usize = 15;
+
+// Upper bound on seed search result pages downloaded in HTTP mode
+const MAX_SEED_PAGES: usize = 20;
+
+/// Visits a product detail page and reads stock, variants, weight, condition,
+/// min order and description. `None` when the page could not be opened.
+pub(crate) async fn scrape_product_detail<B: Browser>(
+    driver: &B,
+    sel: &Selectors,
+    product_url: &str,
+    waits: &Waits,
+) -> Option<ProductDetail> {
+    if let Err(e) = open_page(driver, product_url, &[&sel.css("product_ready")], waits).await {
+        info!("Failed to navigate to product page: {}", e);
+        return None;
+    }
 
-use crate::models::{Product, QueryResult, ShopResults};
+    let detail = read_product_detail(driver, sel, waits).await;
+    info!(
+        "Detail for {}: stock {:?}, {} variants, weight {:?}",
+        product_url,
+        detail.stock,
+        detail.variants.len(),
+        detail.weight_grams
+    );
+    Some(detail)
+}
+
+// Wait for the page, parse the visible text, read the description, then click
+// through the variant buttons reading the price each time.
+async fn read_product_detail<B: Browser>(driver: &B, sel: &Selectors, waits: &Waits) -> ProductDetail {
+    any_selector(driver, &[&sel.css("product_ready")], waits.timeout).await;
+
+    let text = match driver.find(Locator::Css("body")).await {
+        Some(body) => body.text().await,
+        None => String::new(),
+    };
+    let mut detail = parse_product_detail(&text);
+    // Only the stock label counts; no label leaves the stock unknown
+    detail.stock = sel
+        .read(driver, "product_stock")
+        .await
+        .and_then(|label| parse_stock(&label));
+
+    // Description: dedicated element first, then the block after the "Deskripsi" heading
+    detail.description = sel
+        .read(driver, "product_description")
+        .await
+        .map(|d| d.trim().to_string())
+        .filter(|d| !d.is_empty());
+
+    // Variants re-render on click, so look the buttons up again every time
+    let variant_count = sel
+        .find_all(driver, "product_variant")
+        .await
+        .len()
+        .min(MAX_VARIANTS);
+    for index in 0..variant_count {
+        let buttons = sel.find_all(driver, "product_variant").await;
+        let button = match buttons.get(index) {
+            Some(b) => b,
+            None => break,
+        };
+
+        let mut name = button.text().await;
+        if name.trim().is_empty() {
+            name = button.attr("aria-label").await.unwrap_or_default();
+        }
+        if name.trim().is_empty() {
+            continue;
+        }
+
+        // Disabled buttons are out-of-stock variants; keep them without a price
+        let enabled = button.is_enabled().await;
+        let price = if enabled && button.click().await.is_ok() {
+            // The price is fetched again for the selected variant
+            network_idle(driver, waits).await;
+            sel.read(driver, "product_price").await.filter(|p| !p.is_empty())
+        } else {
+            None
+        };
+
+        detail.variants.push(Variant {
+            name: name.trim().to_string(),
+            price,
+        });
+    }
+
+    detail
+}
+
+// A discovered shop waiting for its in-shop searches
+struct ShopJob {
+    // Tokopedia shop slug, or Shopee shop id
+    slug: String,
+    shop_display: String,
+    // Products the seed queries found in this shop, by query index
+    seed_products: HashMap<usize, Vec<Product>>,
+    // 1-based, of `total`
+    index: usize,
+    total: usize,
+}
+
+// The shop of a seed result: the slug in a Tokopedia product link, the shop id a
+// Shopee card was read with
+fn shop_key(platform: &str, product: &Product) -> Option<String> {
+    let key = match platform {
+        "shopee" => product.shop.as_str(),
+        _ => product
+            .link
+            .strip_prefix("https://www.tokopedia.com/")?
+            .split_once('/')?
+            .0,
+    };
+    (!key.is_empty()).then(|| key.to_string())
+}
+
+// Groups the products of every seed query, by query index, by their shop
+fn group_by_shop(platform: &str, seeds: Vec<(usize, Vec<Product>)>, queries: &[SearchQuery]) -> Vec<ShopJob> {
+    let mut shop_slugs: Vec<String> = Vec::new();
+    let mut shop_names: HashMap<String, String> = HashMap::new();
+    let mut seed_products: HashMap<String, HashMap<usize, Vec<Product>>> = HashMap::new();
+
+    for (query_index, products) in seeds {
+        for prod in products {
+            let Some(slug) = shop_key(platform, &prod) else {
+                continue;
+            };
+            // Shops are only discovered through products that satisfy the seed query's filter
+            if !queries[query_index].filter.matches(&prod) {
+                continue;
+            }
+
+            if !seed_products.contains_key(&slug) {
+                shop_slugs.push(slug.clone());
+            }
+            if !prod.shop.is_empty() {
+                shop_names.entry(slug.clone()).or_insert_with(|| prod.shop.clone());
+            }
+            seed_products
+                .entry(slug)
+                .or_default()
+                .entry(query_index)
+                .or_default()
+                .push(prod);
+        }
+    }
+
+    let total_shops = shop_slugs.len();
+    let mut shops = Vec::new();
+    for (shop_index, slug) in shop_slugs.into_iter().enumerate() {
+        shops.push(ShopJob {
+            shop_display: shop_names
+                .get(&slug)
+                .cloned()
+                .unwrap_or_else(|| slug.clone()),
+            seed_products: seed_products.remove(&slug).unwrap_or_default(),
+            slug,
+            index: shop_index + 1,
+            total: total_shops,
+        });
+    }
+    shops
+}
+
+// The seed queries `options.seed` names, by index. `Rarest` needs counts and is
+// resolved by `rarest`; here it stands for the first query.
+fn seed_indices(options: &ScrapeOptions, queries: &[SearchQuery], platform: &str, progress: &dyn ProgressSink) -> Vec<usize> {
+    let wanted = match &options.seed {
+        SeedMode::First | SeedMode::Rarest => vec![0],
+        SeedMode::Query { index } => vec![*index],
+        SeedMode::Union { indices } => indices.clone(),
+    };
+    let mut seeds: Vec<usize> = Vec::new();
+    for index in wanted {
+        if index >= queries.len() {
+            warning(platform, progress, format!("No query {} to seed from", index + 1));
+        } else if !seeds.contains(&index) {
+            seeds.push(index);
+        }
+    }
+    if seeds.is_empty() {
+        seeds.push(0);
+    }
+    seeds
+}
+
+// The query with the fewest results; ties and queries that couldn't be counted go to
+// the earlier query. Counts of the first page only can't tell large results apart,
+// which is reported rather than trusted silently.
+fn rarest(
+    queries: &[SearchQuery],
+    counts: &[Option<ResultCount>],
+    platform: &str,
+    progress: &dyn ProgressSink,
+) -> usize {
+    for (q, count) in queries.iter().zip(counts) {
+        info!("[{}] {}: {:?}", platform, q.text, count);
+    }
+    let chosen = counts
+        .iter()
+        .enumerate()
+        .filter_map(|(index, count)| count.map(|count| (count.total, index)))
+        .min()
+        .map(|(_, index)| index);
+    let Some(chosen) = chosen else {
+        warning(platform, progress, format!("No query could be counted; seeding from {}", queries[0].text));
+        return 0;
+    };
+    if counts[chosen].is_some_and(|count| count.at_least) {
+        warning(
+            platform,
+            progress,
+            format!(
+                "Only the first result page could be counted, so {} may not be the rarest query",
+                queries[chosen].text
+            ),
+        );
+    }
+    chosen
+}
+
+// First page of a query's results on the marketplace's search
+fn search_url(platform: &str, query: &SearchQuery) -> String {
+    match platform {
+        "shopee" => format!(
+            "https://shopee.co.id/search?keyword={}{}",
+            urlencoding::encode(&query.text),
+            query.filter.shopee_params()
+        ),
+        _ => format!(
+            "https://www.tokopedia.com/search?q={}{}",
+            urlencoding::encode(&query.text),
+            query.filter.tokopedia_params()
+        ),
+    }
+}
+
+// The seed queries of a run, by index
+struct Seeds {
+    indices: Vec<usize>,
+    // The browser still shows the first results page of the only seed, opened to
+    // count it
+    shown: bool,
+}
+
+impl Seeds {
+    fn new(indices: Vec<usize>) -> Self {
+        Seeds { indices, shown: false }
+    }
+}
+
+// The seed queries to discover shops with. For `Rarest`, the first page of every
+// query is opened to count its results; the seed search goes on from that page when
+// the rarest query was counted last.
+async fn choose_seeds<B: Browser>(
+    driver: &B,
+    platform: &str,
+    queries: &[SearchQuery],
+    options: &ScrapeOptions,
+    sel: &Selectors,
+    waits: &Waits,
+    progress: &dyn ProgressSink,
+) -> Seeds {
+    if options.seed != SeedMode::Rarest || queries.len() < 2 {
+        return Seeds::new(seed_indices(options, queries, platform, progress));
+    }
+    let (item, empty) = match platform {
+        "shopee" => (sel.css("search_item"), sel.css("search_empty")),
+        _ => (sel.css("seed_card"), sel.css("empty_state")),
+    };
+    let items_or_empty = [item.as_str(), empty.as_str()];
+
+    let mut counts = Vec::new();
+    let mut shows_results = false;
+    for q in queries {
+        let loaded = open_page(driver, &search_url(platform, q), &items_or_empty, waits).await;
+        shows_results = matches!(loaded, Ok(0));
+        let count = match loaded {
+            Ok(0) => {
+                stable_count(driver, &item, waits).await;
+                let html = match driver.find(Locator::Css("body")).await {
+                    Some(body) => body.outer_html().await,
+                    None => String::new(),
+                };
+                Some(extract::result_count(&html, sel, &q.filter))
+            }
+            Ok(_) => Some(ResultCount {
+                total: 0,
+                at_least: false,
+            }),
+            Err(e) => {
+                info!("Not counting {}: {}", q.text, e);
+                None
+            }
+        };
+        counts.push(count);
+    }
+    let index = rarest(queries, &counts, platform, progress);
+    Seeds {
+        indices: vec![index],
+        shown: shows_results && index == queries.len() - 1,
+    }
+}
+
+// Products of every seed search that loaded, by query index. Only when every seed
+// failed is there nothing to discover shops from.
+fn seed_results<E: fmt::Display>(
+    platform: &str,
+    queries: &[SearchQuery],
+    results: Vec<(usize, Result<Vec<Product>, E>)>,
+    progress: &dyn ProgressSink,
+) -> Result<Vec<(usize, Vec<Product>)>, E> {
+    let several = results.len() > 1;
+    let mut seeds = Vec::new();
+    let mut last_error = None;
+    for (index, result) in results {
+        match result {
+            Ok(products) => seeds.push((index, products)),
+            Err(e) => {
+                if several {
+                    warning(platform, progress, format!("Seed search {} failed: {}", queries[index].text, e));
+                }
+                last_error = Some(e);
+            }
+        }
+    }
+    match last_error {
+        Some(e) if seeds.is_empty() => Err(e),
+        _ => Ok(seeds),
+    }
+}
+
+// What every shop of a run shares
+struct ShopContext<'r> {
+    tracker: &'r ProgressTracker<'r>,
+    progress: &'r dyn ProgressSink,
+    queries: &'r [SearchQuery],
+    limit: usize,
+    waits: &'r Waits,
+    sel: &'r Selectors,
+    // Results of earlier runs, when the result cache is on
+    cache: Option<&'r ResultCache>,
+}
 
 // Tokopedia scraper implementation
 pub struct TokopediaScraper;
 
 impl TokopediaScraper {
+    /// Collects shops from the seed queries' results, then searches the other
+    /// queries in up to `options.max_tabs` shops at once.
     pub async fn scrape(
         driver: &WebDriver,
-        queries: &[String],
-        window: &tauri::Window,
+        queries: &[SearchQuery],
+        progress: &dyn ProgressSink,
         limit: usize,
+        options: &ScrapeOptions,
+        sel: &Selectors,
     ) -> Result<Vec<ShopResults>, String> {
         info!("Starting Tokopedia scraping with limit {}", limit);
+        let tracker = ProgressTracker::new("tokopedia", progress);
+        let waits = Waits::from_options(options);
 
         if queries.is_empty() {
             return Ok(Vec::new());
         }
 
-        let first_query = &queries[0];
+        let seeds = choose_seeds(driver, "tokopedia", queries, options, sel, &waits, progress).await;
+        let shops = Self::collect_shops(driver, queries, &seeds, limit, sel, &waits, &tracker, progress).await?;
+        let pool = TabPool::open(
+            driver,
+            options.max_tabs,
+            Duration::from_millis(options.politeness_ms),
+        )
+        .await?;
+
+        let context = ShopContext {
+            tracker: &tracker,
+            progress,
+            queries,
+            limit,
+            waits: &waits,
+            sel,
+            cache: options.cache.as_deref(),
+        };
+        let jobs: Vec<_> = shops
+            .into_iter()
+            .map(|shop| Self::scrape_shop(&pool, &context, shop))
+            .collect();
+
+        // Every shop waits for a free tab, so at most `max_tabs` shops are in progress
+        let grouped: Vec<ShopResults> = stream::iter(jobs)
+            .buffer_unordered(pool.size())
+            .collect()
+            .await;
+        pool.close().await;
+
+        Ok(grouped)
+    }
+
+    // Groups the first `limit` results of every seed query by shop. Err when no seed
+    // search loaded.
+    #[allow(clippy::too_many_arguments)]
+    async fn collect_shops<B: Browser>(
+        driver: &B,
+        queries: &[SearchQuery],
+        seeds: &Seeds,
+        limit: usize,
+        sel: &Selectors,
+        waits: &Waits,
+        tracker: &ProgressTracker<'_>,
+        progress: &dyn ProgressSink,
+    ) -> Result<Vec<ShopJob>, String> {
+        let mut results = Vec::new();
+        for &index in &seeds.indices {
+            let seed_query = &queries[index];
+            let opened = match seeds.shown {
+                true => Ok(()),
+                false => Self::open_seed_search(driver, seed_query, sel, waits).await,
+            };
+            let products = match opened {
+                Ok(()) => Ok(Self::search_seed(driver, limit, sel, waits, tracker, progress).await),
+                Err(e) => Err(e),
+            };
+            results.push((index, products));
+        }
+        let seeds = seed_results("tokopedia", queries, results, progress)?;
+        Ok(group_by_shop("tokopedia", seeds, queries))
+    }
+
+    // Searches a seed query: through the search box, or the search URL when the box
+    // isn't there. Err when neither the results nor the empty state could be loaded.
+    async fn open_seed_search<B: Browser>(
+        driver: &B,
+        seed_query: &SearchQuery,
+        sel: &Selectors,
+        waits: &Waits,
+    ) -> Result<(), String> {
+        let seed_card = sel.css("seed_card");
+        let empty_state = sel.css("empty_state");
+        let results_or_empty = [seed_card.as_str(), empty_state.as_str()];
 
         // Navigate to Tokopedia
-        let _ = driver.goto("https://www.tokopedia.com/").await;
-        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+        if let Err(e) = open_page(driver, "https://www.tokopedia.com/", &[&sel.css("search_input")], waits).await {
+            info!("{}", e);
+        }
 
-        // Try search with input first
-        if Self::perform_site_search(driver, first_query)
-            .await
-            .is_err()
-        {
-            let first_url = format!(
-                "https://www.tokopedia.com/search?q={}",
-                urlencoding::encode(first_query)
-            );
-            let _ = driver.goto(&first_url).await;
-            tokio::time::sleep(std::time::Duration::from_secs(2)).await;
-        } else {
-            tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+        // Try search with input first, unless the query has filters that only the URL can carry.
+        // Recorded sessions load every page by URL, so replay can find it again.
+        let filter_params = seed_query.filter.tokopedia_params();
+        let typed = filter_params.is_empty()
+            && waits.archive.is_none()
+            && Self::perform_site_search(driver, sel, &seed_query.text)
+                .await
+                .is_ok()
+            && any_selector(driver, &results_or_empty, waits.timeout)
+                .await
+                .is_some();
+        if !typed {
+            // Without seed results there are no shops to search
+            open_page(driver, &search_url("tokopedia", seed_query), &results_or_empty, waits)
+                .await
+                .map_err(|e| format!("Tokopedia search did not load: {}", e))?;
         }
+        Ok(())
+    }
+
+    // The first `limit` results of the seed search being shown, scrolling for more
+    async fn search_seed<B: Browser>(
+        driver: &B,
+        limit: usize,
+        sel: &Selectors,
+        waits: &Waits,
+        tracker: &ProgressTracker<'_>,
+        progress: &dyn ProgressSink,
+    ) -> Vec<Product> {
+        let seed_card = sel.css("seed_card");
+        let mut card_count = stable_count(driver, &seed_card, waits).await;
+        page_loaded(driver, "tokopedia", progress).await;
 
         // Scroll and load more for the first result to get enough shops
-        let mut first_cards = Vec::new();
         let mut scroll_attempts = 0;
         let max_scroll_attempts = 20; // Prevent infinite loop
 
-        loop {
+        let mut first_cards = loop {
             // Get current cards
-             let current_cards = match driver
-                .find_all(By::Css("div[data-ssr=\"contentProductsSRPSSR\"] a"))
-                .await
-            {
-                Ok(v) => v,
-                Err(_) => Vec::new(),
-            };
+            let current_cards = sel.find_all(driver, "seed_card").await;
+            tracker.collecting(
+                scroll_attempts + 1,
+                Some(max_scroll_attempts + 1),
+                limit,
+                current_cards.len().min(limit),
+            );
 
             if current_cards.len() >= limit || scroll_attempts >= max_scroll_attempts {
-                 first_cards = current_cards;
-                 break;
+                 break current_cards;
             }
 
             // Scroll down
-             let _ = driver
-                .execute("window.scrollTo(0, document.body.scrollHeight);", vec![])
+             driver
+                .run("window.scrollTo(0, document.body.scrollHeight);", vec![])
                 .await;
-            tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+            let mut loaded = stable_count(driver, &seed_card, waits).await;
             
             // Check for "Muat Lebih Banyak" button
-            // Note: Selector might need adjustment based on actual site
-             if let Ok(button) = driver.find(By::XPath("//button[contains(text(), 'Muat Lebih Banyak')]")).await {
-                 if button.is_displayed().await.unwrap_or(false) {
+             if let Some(button) = sel.find(driver, "load_more").await {
+                 if button.is_displayed().await {
                      let _ = button.click().await;
-                     tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+                     loaded = stable_count(driver, &seed_card, waits).await;
                  }
              }
 
             scroll_attempts += 1;
-        }
+
+            // Neither scrolling nor the button loaded anything new: the results ran out
+            if loaded <= card_count {
+                info!("No more Tokopedia results after {} cards", loaded);
+                scroll_attempts = max_scroll_attempts;
+            }
+            card_count = loaded;
+        };
         
         // Take only up to limit
         if first_cards.len() > limit {
              first_cards.truncate(limit);
         }
 
-        let mut shop_slugs: HashSet<String> = HashSet::new();
-        let mut shop_names: HashMap<String, String> = HashMap::new();
-        let mut first_products_map: HashMap<String, Vec<Product>> = HashMap::new();
-
+        let mut products = Vec::new();
         for c in first_cards {
-            let link = match c.attr("href").await {
-                Ok(opt) => opt.unwrap_or_default(),
-                Err(_) => String::new(),
-            };
+            if let Some(product) = read_card(&c, sel).await {
+                products.push(product);
+            }
+        }
+        products
+    }
 
-            if link.contains("/product?perpage=") {
-                continue;
+    // Profile and every query of one shop, in one tab of the pool
+    async fn scrape_shop(pool: &TabPool<'_>, context: &ShopContext<'_>, shop: ShopJob) -> ShopResults {
+        let ShopContext {
+            tracker,
+            progress,
+            queries,
+            ..
+        } = *context;
+        let tab = pool.acquire().await;
+        let ShopJob {
+            slug,
+            shop_display,
+            mut seed_products,
+            index,
+            total,
+        } = shop;
+        let shop_url = format!("https://www.tokopedia.com/{}", slug);
+
+        progress.emit(ProgressEvent::ShopStarted {
+            platform: "tokopedia".to_string(),
+            shop: shop_display.clone(),
+            index,
+            total,
+        });
+
+        tracker.searching(index, total, 1, queries.len());
+        let mut profile = Self::scrape_shop_profile(&tab, context, &shop_url).await;
+
+        let mut qresults: Vec<QueryResult> = Vec::new();
+
+        for (query_index, q) in queries.iter().enumerate() {
+            if query_index > 0 {
+                tracker.searching(index, total, query_index + 1, queries.len());
             }
-            let link = if link.starts_with("//") {
-                format!("https:{}", link)
-            } else if link.starts_with('/') {
-                format!("https://www.tokopedia.com{}", link)
-            } else {
-                link
+            // Seed query products were found by the global search; the shop
+            // doesn't need to be searched again for them
+            let result = match seed_products.remove(&query_index) {
+                Some(products) => QueryResult::new(q, products),
+                None => Self::shop_query(&tab, context, &slug, &shop_display, q).await,
             };
 
-            let marker = "https://www.tokopedia.com/";
-            if let Some(rest) = link.strip_prefix(marker) {
-                if let Some((slug, _)) = rest.split_once('/') {
-                    if !slug.is_empty() {
-                        let spans = match c
-                            .find_all(By::Css(
-                                "div:nth-child(1) > div:nth-child(2) > div:nth-child(1) span",
-                            ))
-                            .await
-                        {
-                            Ok(v) => v,
-                            Err(_) => Vec::new(),
-                        };
-                        
-                        let mut name = String::new();
-                        for s in spans.into_iter().take(20) {
-                            let span_text = s.text().await.unwrap_or_default();
-                            // info!("span: {}", span_text);
-                            if !span_text.is_empty() && name.is_empty() {
-                                name = span_text;
-                            }
-                        }
-
-                        let price = match c
-                            .find(By::Css("div > div:nth-child(2) > div:nth-child(2)"))
-                            .await
-                        {
-                            Ok(el) => el.text().await.unwrap_or_default(),
-                            Err(_) => String::new(),
-                        };
-                        let shop_display = match c.find(By::Css("span.flip")).await {
-                            Ok(el) => el.text().await.unwrap_or_default(),
-                            Err(_) => String::new(),
-                        };
-                        let location = match c
-                            .find(By::Css(
-                                "div > div:nth-child(2) > div:nth-child(3) span:nth-child(2)",
-                            ))
-                            .await
-                        {
-                            Ok(el) => el.text().await.unwrap_or_default(),
-                            Err(_) => String::new(),
-                        };
-                        let photo = match c.find(By::Css("img[alt=\"product-image\"]")).await {
-                            Ok(el) => el.attr("src").await.unwrap_or(None).unwrap_or_default(),
-                            Err(_) => String::new(),
-                        };
-
-                        shop_slugs.insert(slug.to_string());
-                        if !shop_display.is_empty() {
-                            shop_names.insert(slug.to_string(), shop_display.clone());
-                        }
+            progress.emit(ProgressEvent::QueryFinished {
+                platform: "tokopedia".to_string(),
+                shop: shop_display.clone(),
+                query: q.text.clone(),
+                products: result.products.len(),
+            });
+            tracker.query_finished(result.products.len());
+            qresults.push(result);
+        }
 
-                        let prod = Product {
-                            name,
-                            price,
-                            shop: shop_display.clone(),
-                            location,
-                            photo,
-                            link: link.clone(),
-                        };
-                        first_products_map
-                            .entry(slug.to_string())
-                            .or_insert_with(Vec::new)
-                            .push(prod);
-                    }
-                }
-            }
+        fill_city_from_products(&mut profile, &qresults);
+        // The pool closes its tabs without leaving their last page through `open_page`
+        if let Some(archive) = &context.waits.archive {
+            archive.leave(&tab).await;
         }
 
-        let mut grouped: Vec<ShopResults> = Vec::new();
+        let shop_result = ShopResults {
+            shop_name: shop_display,
+            shop_url,
+            platform: "tokopedia".to_string(),
+            results: qresults,
+            profile: Some(profile),
+            basket: None,
+        };
 
-        for slug in shop_slugs.into_iter() {
-            let shop_url = format!("https://www.tokopedia.com/{}", slug);
-            let shop_display = shop_names
-                .get(&slug)
-                .cloned()
-                .unwrap_or_else(|| slug.clone());
+        // Report progress real-time
+        tracker.shop_finished();
+        progress.emit(ProgressEvent::ShopFinished {
+            shop: Box::new(shop_result.clone()),
+        });
 
-            let mut qresults: Vec<QueryResult> = Vec::new();
+        shop_result
+    }
 
-            for q in queries.iter() {
-                let mut products: Vec<Product> = Vec::new();
+    /// Searches `queries` in shops of an earlier run, up to `options.max_tabs` shops at
+    /// once, and returns the shops with the new results added. A query a shop already
+    /// has results for isn't searched again.
+    pub async fn extend(
+        driver: &WebDriver,
+        shops: Vec<ShopResults>,
+        queries: &[SearchQuery],
+        progress: &dyn ProgressSink,
+        limit: usize,
+        options: &ScrapeOptions,
+        sel: &Selectors,
+    ) -> Result<Vec<ShopResults>, String> {
+        info!("Extending {} Tokopedia shop(s) with {} query(s)", shops.len(), queries.len());
+        let tracker = ProgressTracker::new("tokopedia", progress);
+        let waits = Waits::from_options(options);
+        let pool = TabPool::open(
+            driver,
+            options.max_tabs,
+            Duration::from_millis(options.politeness_ms),
+        )
+        .await?;
+
+        let context = ShopContext {
+            tracker: &tracker,
+            progress,
+            queries,
+            limit,
+            waits: &waits,
+            sel,
+            cache: options.cache.as_deref(),
+        };
+        let total = shops.len();
+        let jobs: Vec<_> = shops
+            .into_iter()
+            .enumerate()
+            .map(|(i, shop)| Self::extend_shop(&pool, &context, shop, i + 1, total))
+            .collect();
+
+        // Kept in the order of the earlier run
+        let extended: Vec<ShopResults> = stream::iter(jobs)
+            .buffered(pool.size())
+            .collect()
+            .await;
+        pool.close().await;
+
+        Ok(extended)
+    }
 
-                // First query products don't need to be searched again in the shop
-                // But if we want to support limit per shop/query, we might need to adjust logic.
-                // For now, assuming first query items found in global search are sufficient/starter.
-                // If the user wants more items from the shop for the first query, we might need to visit shop page too.
-                // CURRENT LOGIC: use what we found in global search for first query.
-                if q == first_query {
-                    if let Some(v) = first_products_map.get(&slug) {
-                        products = v.clone();
-                    }
-                    // If we need more items for this query from this shop specifically, we should search in shop.
-                    // But usually global search is enough to identify shops.
-                } else {
-                    let shop_page = format!("https://www.tokopedia.com/{}", slug);
-                    let _ = driver.goto(&shop_page).await;
-
-                    // Wait for shop name to appear
-                    {
-                        use std::time::Duration as StdDuration;
-                        let start = std::time::Instant::now();
-                        let timeout = StdDuration::from_secs(6);
-                        loop {
-                            if driver
-                                .find(By::Css("h1[data-testid=\"shopNameHeader\"]"))
-                                .await
-                                .is_ok()
-                            {
-                                break;
-                            }
-
-                            if start.elapsed() >= timeout {
-                                info!("Timed out waiting for shopNameHeader to load");
-                                break;
-                            }
-                            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
-                        }
-                    }
-                    // Wait for products to load.
-                    let mut used_input = false;
-                    if Self::perform_site_search(driver, &q).await.is_ok() {
-                        used_input = true;
-                        info!("Performed search via input for shop {} query {}", slug, q);
-                        {
-                            use std::time::Duration as StdDuration;
-                            let start = std::time::Instant::now();
-                            let timeout = StdDuration::from_secs(6);
-                            loop {
-                                if driver
-                                    .find(By::Css("img[alt=\"product-image\"]"))
-                                    .await
-                                    .is_ok()
-                                {
-                                    info!("Found products");
-                                    break;
-                                }
-
-                                if driver
-                                    .find(By::Css("div[class=\"unf-emptystate-img\"]"))
-                                    .await
-                                    .is_ok()
-                                {
-                                    info!("emptystate");
-                                    break;
-                                } else {
-                                    if start.elapsed() >= timeout {
-                                        info!("Timed out waiting for products to load 1");
-                                        break;
-                                    }
-                                }
-
-                                tokio::time::sleep(std::time::Duration::from_millis(500)).await;
-                            }
-                        }
-                    }
+    // The queries `shop` is missing, in one tab of the pool
+    async fn extend_shop(
+        pool: &TabPool<'_>,
+        context: &ShopContext<'_>,
+        mut shop: ShopResults,
+        index: usize,
+        total: usize,
+    ) -> ShopResults {
+        let ShopContext {
+            tracker,
+            progress,
+            ..
+        } = *context;
+        let missing = missing_queries(&shop, context.queries);
+        if missing.is_empty() {
+            return shop;
+        }
+        let Some(slug) = extract::tokopedia_shop_slug(&shop.shop_url) else {
+            warning("tokopedia", progress, format!("Not a Tokopedia shop: {}", shop.shop_url));
+            return shop;
+        };
+        let tab = pool.acquire().await;
 
-                    tokio::time::sleep(std::time::Duration::from_millis(1000)).await;
-
-                    // Failed to search using input, try direct URL (risky redirect)
-                    if !used_input {
-                        let url = format!(
-                            "https://www.tokopedia.com/{}/product?q={}&srp_page_title={}&navsource=shop&srp_component_id=02.01.00.00",
-                            slug,
-                            urlencoding::encode(&q),
-                            urlencoding::encode(&shop_display)
-                        );
-                        let _ = driver.goto(&url).await;
-                        info!("Fallback URL PENCARIAN : {}", url);
-                    }
+        progress.emit(ProgressEvent::ShopStarted {
+            platform: "tokopedia".to_string(),
+            shop: shop.shop_name.clone(),
+            index,
+            total,
+        });
 
-                    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
-                    
-                    // Implement scrolling for shop search results if needed?
-                    // Usually shop search results are less than global search, but we can try small scroll.
-                    let _ = driver
-                        .execute("window.scrollTo(0, document.body.scrollHeight);", vec![])
-                        .await;
-                    tokio::time::sleep(std::time::Duration::from_millis(1000)).await;
-
-
-                    let cards = match driver
-                        .find_all(By::Css(
-                            r#"[data-ssr="shopSSR"] > div:nth-child(2) a[data-theme="default"]"#,
-                        ))
-                        .await
-                    {
-                        Ok(v) => v,
-                        Err(_) => Vec::new(),
-                    };
+        for (query_index, q) in missing.iter().enumerate() {
+            tracker.searching(index, total, query_index + 1, missing.len());
+            let result = Self::shop_query(&tab, context, &slug, &shop.shop_name, q).await;
 
-                    for c in cards.into_iter().take(limit) { // Apply limit here too
-                        let link = match c.attr("href").await {
-                            Ok(opt) => opt.unwrap_or_default(),
-                            Err(_) => String::new(),
-                        };
-                        if link.starts_with(&format!("/{}/product?perpage", slug)) {
-                            continue;
-                        }
-                        let link = if link.starts_with("//") {
-                            format!("https:{}", link)
-                        } else if link.starts_with('/') {
-                            format!("https://www.tokopedia.com{}", link)
-                        } else {
-                            link
-                        };
-
-                        let spans = match c
-                            .find_all(By::Css(
-                                "div:nth-child(1) > div:nth-child(2) > div:nth-child(1) span",
-                            ))
-                            .await
-                        {
-                            Ok(v) => v,
-                            Err(_) => Vec::new(),
-                        };
-                        
-                        let mut name = String::new();
-                        for s in spans.into_iter().take(20) {
-                            let span_text = s.text().await.unwrap_or_default();
-                            // info!("span: {}", span_text);
-                            if !span_text.is_empty() && name.is_empty() {
-                                name = span_text;
-                            }
-                        }
+            progress.emit(ProgressEvent::QueryFinished {
+                platform: "tokopedia".to_string(),
+                shop: shop.shop_name.clone(),
+                query: q.text.clone(),
+                products: result.products.len(),
+            });
+            tracker.query_finished(result.products.len());
+            shop.results.push(result);
+        }
 
-                        let price = match c
-                            .find(By::Css("div > div:nth-child(2) > div:nth-child(2)"))
-                            .await
-                        {
-                            Ok(el) => el.text().await.unwrap_or_default(),
-                            Err(_) => String::new(),
-                        };
-                        let shop = shop_display.clone();
-                        let location = match c
-                            .find(By::Css(
-                                "div > div:nth-child(2) > div:nth-child(3) span:nth-child(2)",
-                            ))
-                            .await
-                        {
-                            Ok(el) => el.text().await.unwrap_or_default(),
-                            Err(_) => String::new(),
-                        };
-                        let photo = match c.find(By::Css("img[alt=\"product-image\"]")).await {
-                            Ok(el) => el.attr("src").await.unwrap_or(None).unwrap_or_default(),
-                            Err(_) => String::new(),
-                        };
-
-                        products.push(Product {
-                            name,
-                            price,
-                            shop: shop.clone(),
-                            location,
-                            photo,
-                            link,
-                        });
-                    }
+        if let Some(archive) = &context.waits.archive {
+            archive.leave(&tab).await;
+        }
+
+        tracker.shop_finished();
+        progress.emit(ProgressEvent::ShopFinished { shop: Box::new(shop.clone()) });
+        shop
+    }
+
+    // One query in one shop: from the result cache, or searched in the tab
+    async fn shop_query(
+        tab: &Tab<'_, '_>,
+        context: &ShopContext<'_>,
+        slug: &str,
+        shop_display: &str,
+        q: &SearchQuery,
+    ) -> QueryResult {
+        if let Some(cached) = context.cache.and_then(|c| c.get("tokopedia", slug, q)) {
+            return cached;
+        }
+        let result = match Self::search_in_shop(tab, context, slug, shop_display, q).await {
+            Ok(products) => QueryResult::new(q, products),
+            Err(e) => {
+                warning("tokopedia", context.progress, format!("Failed to search {} in {}: {}", q.text, slug, e));
+                QueryResult::failed(q, e)
+            }
+        };
+        if let Some(cache) = context.cache {
+            cache.put("tokopedia", slug, q, &result);
+        }
+        result
+    }
+
+    // Searches one query in the shop: through the shop's search box, or the shop
+    // search URL when the box isn't there. Err when neither results nor the empty
+    // state could be loaded.
+    async fn search_in_shop(
+        tab: &Tab<'_, '_>,
+        context: &ShopContext<'_>,
+        slug: &str,
+        shop_display: &str,
+        q: &SearchQuery,
+    ) -> Result<Vec<Product>, String> {
+        let ShopContext {
+            progress,
+            limit,
+            waits,
+            sel,
+            ..
+        } = *context;
+        let shop_page = format!("https://www.tokopedia.com/{}", slug);
+        let product_image = sel.css("shop_product_image");
+        let empty_state = sel.css("empty_state");
+        let products_or_empty = [product_image.as_str(), empty_state.as_str()];
+
+        // Wait for shop name to appear; the search URL below still works without it
+        let mut used_input = false;
+        match open_page(tab, &shop_page, &[&sel.css("shop_header")], waits).await {
+            // Recorded sessions use the search URL below
+            Ok(_) if waits.archive.is_some() => {}
+            Ok(_) => {
+                tab.wait_turn().await;
+                if let Ok(driver) = tab.lock().await {
+                    used_input = Self::perform_site_search(&*driver, sel, &q.text).await.is_ok();
                 }
+            }
+            Err(e) => info!("{}", e),
+        }
 
-                qresults.push(QueryResult {
-                    query: q.clone(),
-                    products,
-                });
+        // Wait for products to load.
+        if used_input {
+            info!("Performed search via input for shop {} query {}", slug, q.text);
+            match any_selector(tab, &products_or_empty, waits.timeout).await {
+                Some(0) => info!("Found products"),
+                Some(_) => info!("emptystate"),
+                None => used_input = false,
             }
+        }
 
-            let shop_result = ShopResults {
-                shop_name: shop_display.clone(),
-                shop_url: shop_url.clone(),
-                platform: "tokopedia".to_string(),
-                results: qresults,
+        // Failed to search using input, try direct URL (risky redirect)
+        if !used_input {
+            let url = format!(
+                "https://www.tokopedia.com/{}/product?q={}&srp_page_title={}&navsource=shop&srp_component_id=02.01.00.00",
+                slug,
+                urlencoding::encode(&q.text),
+                urlencoding::encode(shop_display)
+            );
+            info!("Fallback URL PENCARIAN : {}", url);
+            open_page(tab, &url, &products_or_empty, waits).await?;
+        }
+
+        // Implement scrolling for shop search results if needed?
+        // Usually shop search results are less than global search, but we can try small scroll.
+        if let Ok(driver) = tab.lock().await {
+            let _ = driver
+                .execute("window.scrollTo(0, document.body.scrollHeight);", vec![])
+                .await;
+        }
+        stable_count(tab, &sel.css("shop_card"), waits).await;
+
+        let driver = tab.lock().await?;
+        page_loaded(&*driver, "tokopedia", progress).await;
+
+        let cards = sel.find_all(&*driver, "shop_card").await;
+
+        let mut products: Vec<Product> = Vec::new();
+        for c in cards.into_iter().take(limit) { // Apply limit here too
+            let Some(mut product) = read_card(&c, sel).await else {
+                continue;
             };
+            product.shop = shop_display.to_string();
+            if q.filter.matches(&product) {
+                products.push(product);
+            }
+        }
 
-            // Emit progress real-time
-            let _ = window
-                .emit("scrape:progress", shop_result.clone())
-                .map_err(|e| e.to_string());
+        Ok(products)
+    }
 
-            grouped.push(shop_result);
+    /// Visits the shop page and reads rating, reviews, response time, join date,
+    /// badges and open/online status from the header around the shop name.
+    async fn scrape_shop_profile(tab: &Tab<'_, '_>, context: &ShopContext<'_>, shop_url: &str) -> ShopProfile {
+        let sel = context.sel;
+        // Wait for shop name to appear
+        if let Err(e) = open_page(tab, shop_url, &[&sel.css("shop_header")], context.waits).await {
+            info!("No shop profile: {}", e);
+            return ShopProfile::default();
         }
+        let driver = match tab.lock().await {
+            Ok(driver) => driver,
+            Err(_) => return ShopProfile::default(),
+        };
 
-        // Emit done
-        let _ = window.emit("scrape:done", ()).map_err(|e| e.to_string());
+        // The header block holding name, badges, rating and status sits a few levels above the h1
+        let header = match sel.find(&*driver, "shop_profile").await {
+            Some(el) => el,
+            None => return ShopProfile::default(),
+        };
 
-        Ok(grouped)
+        let text = header.text().await.unwrap_or_default();
+        let labels = image_labels(&header, sel).await;
+        parse_shop_profile(&text, &labels)
     }
 
-    async fn perform_site_search(driver: &WebDriver, query: &str) -> Result<(), ()> {
+    async fn perform_site_search<B: Browser>(driver: &B, sel: &Selectors, query: &str) -> Result<(), ()> {
         // Cari input
-        let sel = r#"input[data-unify="Search"][type="search"]"#;
-        if let Ok(el) = driver.find(By::Css(sel)).await {
-            if el.is_displayed().await.unwrap_or(false) {
+        if let Some(el) = sel.find(driver, "search_input").await {
+            if el.is_displayed().await {
                 let _ = el.click().await;
-                let _ = el.clear().await;
+                el.clear().await;
                 let _ = el.send_keys(query).await;
+                // The results replace this page; waits mustn't succeed on it meanwhile
+                driver.expect_navigation().await;
                 let _ = el.send_keys("\n").await;
                 return Ok(());
             }
         }
         Err(())
     }
+
+    /// `scrape` without a browser: search and shop pages are downloaded as HTML.
+    /// Err when the seed search can't be downloaded, e.g. because Tokopedia blocks
+    /// it, so the browser can take over; shop pages that fail later become failed
+    /// query results.
+    pub(crate) async fn scrape_http(
+        fetcher: &HttpFetcher,
+        queries: &[SearchQuery],
+        progress: &dyn ProgressSink,
+        limit: usize,
+        options: &ScrapeOptions,
+        sel: &Selectors,
+    ) -> Result<Vec<ShopResults>, FetchError> {
+        info!("Starting Tokopedia HTTP scraping with limit {}", limit);
+        let tracker = ProgressTracker::new("tokopedia", progress);
+        let waits = Waits::from_options(options);
+
+        if queries.is_empty() {
+            return Ok(Vec::new());
+        }
+        let seed_card = sel.css("seed_card");
+        let empty_state = sel.css("empty_state");
+        let results_or_empty = [seed_card.as_str(), empty_state.as_str()];
+
+        // First result pages downloaded to count them, by query index
+        let mut probes: HashMap<usize, String> = HashMap::new();
+        let seeds = match options.seed {
+            SeedMode::Rarest if queries.len() > 1 => {
+                let mut counts = Vec::new();
+                for (index, q) in queries.iter().enumerate() {
+                    let count = match fetcher.page(&search_url("tokopedia", q), &results_or_empty).await {
+                        Ok((html, 0)) => {
+                            let count = extract::result_count(&html, sel, &q.filter);
+                            probes.insert(index, html);
+                            Some(count)
+                        }
+                        Ok(_) => Some(ResultCount {
+                            total: 0,
+                            at_least: false,
+                        }),
+                        Err(e) => {
+                            info!("Not counting {}: {}", q.text, e);
+                            None
+                        }
+                    };
+                    counts.push(count);
+                }
+                vec![rarest(queries, &counts, "tokopedia", progress)]
+            }
+            _ => seed_indices(options, queries, "tokopedia", progress),
+        };
+        let mut results = Vec::new();
+        for index in seeds {
+            let first_page = probes.remove(&index);
+            let products =
+                Self::search_seed_http(fetcher, &queries[index], limit, sel, &tracker, progress, first_page).await;
+            results.push((index, products));
+        }
+        let seeds = seed_results("tokopedia", queries, results, progress)?;
+
+        let context = ShopContext {
+            tracker: &tracker,
+            progress,
+            queries,
+            limit,
+            waits: &waits,
+            sel,
+            cache: options.cache.as_deref(),
+        };
+        let jobs: Vec<_> = group_by_shop("tokopedia", seeds, queries)
+            .into_iter()
+            .map(|shop| Self::scrape_shop_http(fetcher, &context, shop))
+            .collect();
+
+        // Requests are spaced by the fetcher, so this only overlaps their waiting
+        let grouped: Vec<ShopResults> = stream::iter(jobs)
+            .buffer_unordered(options.max_tabs.max(1))
+            .collect()
+            .await;
+        Ok(grouped)
+    }
+
+    // The first `limit` results of a seed query, one server-rendered page per request
+    async fn search_seed_http(
+        fetcher: &HttpFetcher,
+        seed_query: &SearchQuery,
+        limit: usize,
+        sel: &Selectors,
+        tracker: &ProgressTracker<'_>,
+        progress: &dyn ProgressSink,
+        // The first page, when it was already downloaded to count the results
+        mut first_page: Option<String>,
+    ) -> Result<Vec<Product>, FetchError> {
+        let seed_card = sel.css("seed_card");
+        let empty_state = sel.css("empty_state");
+        let results_or_empty = [seed_card.as_str(), empty_state.as_str()];
+
+        let mut products: Vec<Product> = Vec::new();
+        for page in 1..=MAX_SEED_PAGES {
+            tracker.collecting(page, Some(MAX_SEED_PAGES), limit, products.len().min(limit));
+            let url = format!(
+                "https://www.tokopedia.com/search?q={}&page={}{}",
+                urlencoding::encode(&seed_query.text),
+                page,
+                seed_query.filter.tokopedia_params()
+            );
+            let fetched = match first_page.take() {
+                Some(html) => Ok((html, 0)),
+                None => fetcher.page(&url, &results_or_empty).await,
+            };
+            let html = match fetched {
+                Ok((html, 0)) => html,
+                Ok(_) => break,
+                Err(e) if page == 1 => return Err(e),
+                Err(e) => {
+                    // Keep the shops found on earlier pages
+                    warning("tokopedia", progress, e.to_string());
+                    break;
+                }
+            };
+            progress.emit(ProgressEvent::PageLoaded {
+                platform: "tokopedia".to_string(),
+                url,
+            });
+
+            let found = products.len();
+            for product in extract::page_cards(&html, sel, "seed_card") {
+                if !products.iter().any(|p| p.link == product.link) {
+                    products.push(product);
+                }
+            }
+            if products.len() >= limit || products.len() == found {
+                break;
+            }
+        }
+        products.truncate(limit);
+        Ok(products)
+    }
+
+    // `scrape_shop` over HTTP: the shop page for the profile, then the shop search URL
+    // for every other query
+    async fn scrape_shop_http(fetcher: &HttpFetcher, context: &ShopContext<'_>, shop: ShopJob) -> ShopResults {
+        let ShopContext {
+            tracker,
+            progress,
+            queries,
+            limit,
+            sel,
+            cache,
+            ..
+        } = *context;
+        let ShopJob {
+            slug,
+            shop_display,
+            mut seed_products,
+            index,
+            total,
+        } = shop;
+        let shop_url = format!("https://www.tokopedia.com/{}", slug);
+
+        progress.emit(ProgressEvent::ShopStarted {
+            platform: "tokopedia".to_string(),
+            shop: shop_display.clone(),
+            index,
+            total,
+        });
+
+        tracker.searching(index, total, 1, queries.len());
+        let mut profile = match fetcher.page(&shop_url, &[&sel.css("shop_header")]).await {
+            Ok((html, _)) => extract::tokopedia_shop_profile(&html, sel),
+            Err(e) => {
+                info!("No shop profile: {}", e);
+                ShopProfile::default()
+            }
+        };
+
+        let product_image = sel.css("shop_product_image");
+        let empty_state = sel.css("empty_state");
+        let products_or_empty = [product_image.as_str(), empty_state.as_str()];
+        let mut qresults: Vec<QueryResult> = Vec::new();
+
+        for (query_index, q) in queries.iter().enumerate() {
+            let result = if let Some(products) = seed_products.remove(&query_index) {
+                QueryResult::new(q, products)
+            } else if let Some(cached) = cache.and_then(|c| c.get("tokopedia", &slug, q)) {
+                cached
+            } else {
+                tracker.searching(index, total, query_index + 1, queries.len());
+                let url = format!(
+                    "https://www.tokopedia.com/{}/product?q={}&srp_page_title={}&navsource=shop&srp_component_id=02.01.00.00",
+                    slug,
+                    urlencoding::encode(&q.text),
+                    urlencoding::encode(&shop_display)
+                );
+                let result = match fetcher.page(&url, &products_or_empty).await {
+                    Ok((html, _)) => {
+                        let products = extract::page_cards(&html, sel, "shop_card")
+                            .into_iter()
+                            .take(limit)
+                            .map(|mut product| {
+                                product.shop = shop_display.clone();
+                                product
+                            })
+                            .filter(|product| q.filter.matches(product))
+                            .collect();
+                        QueryResult::new(q, products)
+                    }
+                    Err(e) => {
+                        let e = e.to_string();
+                        warning("tokopedia", progress, format!("Failed to search {} in {}: {}", q.text, slug, e));
+                        QueryResult::failed(q, e)
+                    }
+                };
+                if let Some(cache) = cache {
+                    cache.put("tokopedia", &slug, q, &result);
+                }
+                result
+            };
+
+            progress.emit(ProgressEvent::QueryFinished {
+                platform: "tokopedia".to_string(),
+                shop: shop_display.clone(),
+                query: q.text.clone(),
+                products: result.products.len(),
+            });
+            tracker.query_finished(result.products.len());
+            qresults.push(result);
+        }
+
+        fill_city_from_products(&mut profile, &qresults);
+        let shop_result = ShopResults {
+            shop_name: shop_display,
+            shop_url,
+            platform: "tokopedia".to_string(),
+            results: qresults,
+            profile: Some(profile),
+            basket: None,
+        };
+
+        tracker.shop_finished();
+        progress.emit(ProgressEvent::ShopFinished {
+            shop: Box::new(shop_result.clone()),
+        });
+        shop_result
+    }
 }
 
 // Shopee scraper implementation
 pub struct ShopeeScraper;
 
 impl ShopeeScraper {
-    pub async fn scrape(
-        driver: &WebDriver,
-        queries: &[String],
-        window: &tauri::Window,
+    pub(crate) async fn scrape<B: Browser>(
+        driver: &B,
+        queries: &[SearchQuery],
+        progress: &dyn ProgressSink,
         limit: usize,
+        options: &ScrapeOptions,
+        sel: &Selectors,
+        names: &mut ShopNames,
     ) -> Result<Vec<ShopResults>, String> {
         info!("Starting Shopee scraping with limit {}", limit);
+        let tracker = ProgressTracker::new("shopee", progress);
+        let waits = Waits::from_options(options);
 
         if queries.is_empty() {
             return Ok(Vec::new());
         }
 
-        let first_query = &queries[0];
-
-        // Navigate to Shopee
-        let _ = driver.goto("https://shopee.co.id/").await;
-        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
-
-        // Try search with input first
-        if Self::perform_site_search(&driver, first_query)
-            .await
-            .is_err()
-        {
-            let first_url = format!(
-                "https://shopee.co.id/search?keyword={}",
-                urlencoding::encode(first_query)
-            );
-            let _ = driver.goto(&first_url).await;
-            tokio::time::sleep(std::time::Duration::from_secs(2)).await;
-        } else {
-            tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+        let seeds = choose_seeds(driver, "shopee", queries, options, sel, &waits, progress).await;
+        let mut results = Vec::new();
+        for &index in &seeds.indices {
+            let seed_query = &queries[index];
+            let opened = match seeds.shown {
+                true => Ok(()),
+                false => Self::open_seed_search(driver, seed_query, sel, &waits).await,
+            };
+            let products = match opened {
+                Ok(()) => Ok(Self::search_seed(driver, seed_query, limit, sel, &waits, &tracker, progress).await),
+                Err(e) => Err(e),
+            };
+            results.push((index, products));
         }
+        let seeds = seed_results("shopee", queries, results, progress)?;
+        let shops = group_by_shop("shopee", seeds, queries);
 
-        let mut collected_items = 0;
-        let mut first_cards_collected = Vec::new();
-        let mut page = 0;
+        let mut grouped: Vec<ShopResults> = Vec::new();
+        let context = ShopContext {
+            tracker: &tracker,
+            progress,
+            queries,
+            limit,
+            waits: &waits,
+            sel,
+            cache: options.cache.as_deref(),
+        };
 
-        loop {
-            // Get current page cards
-            let current_cards = match driver
-                .find_all(By::Css(".shopee-search-item-result__item a"))
-                .await
-            {
-                Ok(v) => v,
-                Err(_) => Vec::new(),
-            };
-            
-            // Add to collection
-            // Use ElementId to avoid duplicates if possible, or just collect all and process.
-            // But we need to process links to avoid duplicates.
-            // For simplicitly, let's collect links and process them.
-            // But here we need to keep `WebElement` to extract data later?
-            // Extracting data here might be safer because elements go stale after navigation.
-
-            // Wait... we need to process cards HERE because once we navigate to next page, elements are gone.
-            // But we can just extract the Links and then process them?
-            // No, the original logic extracts data from the card element.
-            // So we should extract data here and accumulate it.
-            
-            // Actually, we need to extract Shop Info to group. 
-            // The existing structure collects `first_cards` (WebElements) then iterates them.
-            // If we navigate, `first_cards` elements become stale.
-            // So we MUST extract data from `current_cards` immediately.
-            
-            // However, the original code logic separates:
-            // 1. Collect first_cards (WebElements)
-            // 2. Iterate first_cards to extract product data & shop info -> `first_products_map` & `shop_slugs`.
-            // 3. Iterate shop_slugs to drill down other queries.
+        for shop in shops {
+            let ShopJob {
+                slug,
+                mut seed_products,
+                index: shop_index,
+                total: total_shops,
+                ..
+            } = shop;
+            let shop_url = extract::shopee_shop_url(&slug);
+            // Shown until the shop page names the shop
+            let shop_display = names.get(&slug).unwrap_or(&slug).to_string();
+
+            progress.emit(ProgressEvent::ShopStarted {
+                platform: "shopee".to_string(),
+                shop: shop_display.clone(),
+                index: shop_index,
+                total: total_shops,
+            });
 
-            // So if I want to paginate:
-            // I should collect data (Product struct + shop_slug) from each page, then aggregate.
-            
-            // Let's refactor the loop to collect PRODUCTS directly.
-            
-            for c in current_cards {
-                 if collected_items >= limit {
-                    break;
-                 }
+            let mut qresults: Vec<QueryResult> = Vec::new();
 
-                 let link = match c.attr("href").await {
-                    Ok(opt) => opt.unwrap_or_default(),
-                    Err(_) => String::new(),
+           
+            for (query_index, q) in queries.iter().enumerate() {
+                tracker.searching(shop_index, total_shops, query_index + 1, queries.len());
+
+                // Seed query products don't need to be searched again in the shop
+                let result = match seed_products.remove(&query_index) {
+                    Some(products) => QueryResult::new(q, products),
+                    None => Self::shop_query(driver, &context, &slug, q).await,
                 };
 
-                // println!("Shopee link found: {}", link);
-                if link.starts_with("/") && !link.contains("find_similar_products") {
-                     first_cards_collected.push(c); // Store element to extract later? NO. Stale.
-                     // We MUST process it now. But wait, `c` is a WebElement on current page.
-                     // The loop later `for c in first_cards.into_iter().take(20)` iterates WebElements.
-                     // This means the migration to pagination requires a bigger refactor of the code below.
-                     // The code below expects `first_cards` to be `Vec<WebElement>`.
-                     // BUT if we navigate to page 2, the page 1 elements are invalid.
-                     // So we cannot store `Vec<WebElement>` from multiple pages.
-                     
-                     // We have to extract ALL info here and store in an intermediate struct, NOT WebElement.
-                     collected_items += 1;
-                }
-            }
-            
-            if collected_items >= limit {
-                break;
+                progress.emit(ProgressEvent::QueryFinished {
+                    platform: "shopee".to_string(),
+                    shop: shop_display.clone(),
+                    query: q.text.clone(),
+                    products: result.products.len(),
+                });
+                tracker.query_finished(result.products.len());
+                qresults.push(result);
             }
 
-            // Check next page
-             let next_button_xpath = "//a[contains(@class, 'shopee-icon-button--right') and not(contains(@class, 'shopee-icon-button--disabled'))]";
-             if let Ok(next_btn) = driver.find(By::XPath(next_button_xpath)).await {
-                 if next_btn.is_displayed().await.unwrap_or(false) {
-                     let _ = next_btn.click().await;
-                     page += 1;
-                     // Wait load
-                     tokio::time::sleep(std::time::Duration::from_secs(3)).await;
-                     continue;
-                 }
-             }
-             
-             // Try URL manipulation if button not found or tricky
-             // Check if we can just go to next page
-             page += 1;
-             let next_url = format!(
-                "https://shopee.co.id/search?keyword={}&page={}",
-                urlencoding::encode(first_query),
-                page
-            );
-             // Verify if we are already at this page or end?
-             // Since we count `collected_items`, maybe we just try to go next page.
-             let _ = driver.goto(&next_url).await;
-             tokio::time::sleep(std::time::Duration::from_secs(3)).await;
-             
-             // Check if we found products
-              if driver.find(By::Css(".shopee-search-item-result__item")).await.is_err() {
-                  break; // No more items
-              }
-        }
-
-        // Wait, if I cannot save WebElement, I must rewrite the processing logic to not use WebElement later.
-        // The current structure is:
-        // 1. Get `first_cards` (Vec<WebElement>).
-        // 2. Loop `first_cards` -> extract `Product`, `shop_slugs`, `first_products_map`.
-        // 3. Loop `shop_slugs` -> process other queries.
-
-        // So I need to:
-        // 1. Create a loop that visits pages.
-        // 2. Inside loop, find elements, extract `Product` immediately.
-        // 3. Store `Product` in `all_extracted_products`.
-        // 4. After loop, populate `shop_slugs`, `shop_names`, `first_products_map` from `all_extracted_products`.
-        
-        // Let's restart the logic for this function section.
-        
-        let mut shop_slugs: HashSet<String> = HashSet::new();
-        let mut shop_names: HashMap<String, String> = HashMap::new();
-        
-        let mut first_products_map: HashMap<String, Vec<Product>> = HashMap::new();
-        let mut all_products: Vec<Product> = Vec::new();
-
-        // Reset to page 0 for extraction loop if we moved?
-        // Actually, better to do the extraction INSIDE the pagination loop.
-        
-        // Re-navigate to start to be safe
-         let first_url = format!(
-            "https://shopee.co.id/search?keyword={}",
-            urlencoding::encode(first_query)
-        );
-        let _ = driver.goto(&first_url).await;
-        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+            let first_link = qresults
+                .iter()
+                .flat_map(|r| &r.products)
+                .next()
+                .map(|p| p.link.clone());
+            if let Some(first_link) = first_link {
+                let (mut profile, page_name) = Self::scrape_shop_profile(driver, sel, &shop_url, &waits).await;
+                fill_city_from_products(&mut profile, &qresults);
+
+                let shop_name = match page_name.or_else(|| names.get(&slug).map(str::to_string)) {
+                    Some(name) => name,
+                    // Neither the shop page nor an earlier run named the shop
+                    None => {
+                        Self::shop_name_from_product(driver, sel, &first_link, &waits)
+                            .await
+                            .unwrap_or_else(|| {
+                                warning("shopee", progress, format!("Shop name not found for shop {}", slug));
+                                slug.clone()
+                            })
+                    }
+                };
+                // The shop id only stands in for a name; a later run may still read it
+                if shop_name != slug {
+                    names.insert(&slug, &shop_name);
+                }
 
-        let mut extracted_count = 0;
-        let mut current_page = 0;
+                let shop_result = ShopResults {
+                    shop_name,
+                    shop_url,
+                    platform: "shopee".to_string(),
+                    results: qresults,
+                    profile: Some(profile),
+                    basket: None,
+                };
 
-        loop {
-            // Get current cards
-             let current_cards = match driver
-                .find_all(By::Css(".shopee-search-item-result__item a"))
-                .await
-            {
-                Ok(v) => v,
-                Err(_) => Vec::new(),
-            };
+                // Report progress real-time
+                progress.emit(ProgressEvent::ShopFinished {
+                    shop: Box::new(shop_result.clone()),
+                });
 
-            for c in current_cards {
-                if extracted_count >= limit {
-                    break;
-                }
+                grouped.push(shop_result);
+            }
+            tracker.shop_finished();
+        }
 
-                let link = match c.attr("href").await {
-                    Ok(opt) => opt.unwrap_or_default(),
-                    Err(_) => String::new(),
-                };
+        Ok(grouped)
+    }
 
-                 // println!("Shopee link found: {}", link);
-                //link bukan /find_similar_products
-                if link.starts_with("/") && !link.contains("find_similar_products") {
-                    let full_link = format!("https://shopee.co.id{}", link);
+    // Searches a seed query: through the search box, or the search URL when the box
+    // isn't there. Err when the search doesn't load.
+    async fn open_seed_search<B: Browser>(
+        driver: &B,
+        seed_query: &SearchQuery,
+        sel: &Selectors,
+        waits: &Waits,
+    ) -> Result<(), String> {
+        let item = sel.css("search_item");
+        let empty = sel.css("search_empty");
+        let items_or_empty = [item.as_str(), empty.as_str()];
 
-                    // Extract product name
-                    let name = match c.find(By::Css(".line-clamp-2.break-words")).await {
-                        Ok(el) => el.text().await.unwrap_or_default(),
-                        Err(_) => String::new(),
-                    };
+        // Navigate to Shopee
+        if let Err(e) = open_page(driver, "https://shopee.co.id/", &[&sel.css("home_ready")], waits).await {
+            info!("{}", e);
+        }
 
-                    // Extract price - Shopee has a specific structure for prices
-                    let price = {
-                        let price_element = match c
-                            .find(By::Css(
-                                "[data-testid=\"a11y-label\"] + div .truncate.text-base\\/5.font-medium",
-                            ))
-                            .await
-                        {
-                            Ok(el) => el,
-                            Err(_) => {
-                                // Alternative selector for price
-                                match c
-                                    .find(By::Css(
-                                        ".text-shopee-primary .truncate.text-base\\/5.font-medium",
-                                    ))
-                                    .await
-                                {
-                                    Ok(el) => el,
-                                    Err(_) => {
-                                        // Try another approach
-                                        match c.find(By::Css(".flex-shrink.min-w-0.mr-1.truncate.text-shopee-primary .truncate.text-base\\/5.font-medium")).await {
-                                            Ok(el) => el,
-                                            Err(_) => {
-                                                // If all else fails, return empty string
-                                                continue; // Skip this item
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        };
-                        price_element.text().await.unwrap_or_default()
-                    };
+        // Try search with input first, unless the query has filters that only the URL can carry.
+        // Recorded sessions load every page by URL, so replay can find it again.
+        let typed = seed_query.filter.shopee_params().is_empty()
+            && waits.archive.is_none()
+            && Self::perform_site_search(driver, sel, &seed_query.text)
+                .await
+                .is_ok()
+            && any_selector(driver, &items_or_empty, waits.timeout)
+                .await
+                .is_some();
+        if !typed {
+            // Without seed results there are no shops to search
+            open_page(driver, &search_url("shopee", seed_query), &items_or_empty, waits)
+                .await
+                .map_err(|e| format!("Shopee search did not load: {}", e))?;
+        }
+        Ok(())
+    }
 
-                    // Extract location
-                    let location = match c
-                        .find(By::Css(
-                            ".text-shopee-black54.font-extralight.text-sp10 .align-middle",
-                        ))
-                        .await
-                    {
-                        Ok(el) => el.text().await.unwrap_or_default(),
-                        Err(_) => String::new(),
-                    };
+    // The first `limit` results of the seed search being shown that satisfy its
+    // filter, page by page
+    async fn search_seed<B: Browser>(
+        driver: &B,
+        seed_query: &SearchQuery,
+        limit: usize,
+        sel: &Selectors,
+        waits: &Waits,
+        tracker: &ProgressTracker<'_>,
+        progress: &dyn ProgressSink,
+    ) -> Vec<Product> {
+        let item = sel.css("search_item");
+        let empty = sel.css("search_empty");
+        let items_or_empty = [item.as_str(), empty.as_str()];
+        let filter_params = seed_query.filter.shopee_params();
+        stable_count(driver, &item, waits).await;
 
-                    // Extract photo
-                    let photo = match c
-                        .find(By::Css(
-                            "img.w-full",
-                        ))
-                        .await
-                    {
-                        Ok(el) => el.attr("src").await.unwrap_or(None).unwrap_or_default(),
-                        Err(_) => {
-                            // Try to get the first image in the product card
-                            match c.find(By::Css("img")).await {
-                                Ok(el) => el.attr("src").await.unwrap_or(None).unwrap_or_default(),
-                                Err(_) => String::new(),
-                            }
-                        }
-                    };
+        let mut all_products: Vec<Product> = Vec::new();
+        let mut current_page = 0;
 
-                    //contoh link shopee https://shopee.co.id/100ribu-dapat-4pcs-Celana-Pendek-Babytery-Calana-Pria-Resleting-Premium-Celana-Running-4pcs-i.124455053.29705222804 maka angka 124455053 adalah shop id
-                    let shop_id = if let Some(rest) = full_link.strip_prefix("https://shopee.co.id/") {
-                        // Buang query params
-                        let path_only = rest.split('?').next().unwrap_or(rest);
-
-                        if let Some((_, ids_part)) = path_only.rsplit_once("-i.") {
-                            if let Some((shop_id_str, _)) = ids_part.split_once('.') {
-                                shop_id_str.to_string()
-                            } else {
-                                "0".to_string()
-                            }
-                        } else {
-                            "0".to_string()
-                        }
-                    } else {
-                        "0".to_string()
-                    };
+        loop {
+            page_loaded(driver, "shopee", progress).await;
+            tracker.collecting(current_page + 1, None, limit, all_products.len());
 
+            for c in sel.find_all(driver, "seed_card").await {
+                if all_products.len() >= limit {
+                    break;
+                }
 
-                     // Store shop info
-                    if !shop_id.is_empty() {
-                        shop_slugs.insert(shop_id.clone());
-                        shop_names.insert(shop_id.clone(), shop_id.clone());
+                // Skips "find similar" links and cards without a price; the shop is
+                // the shop id from the link
+                if let Some(product) = read_card(&c, sel).await {
+                    // Shops are only discovered through products that satisfy the seed query's filter
+                    if seed_query.filter.matches(&product) {
+                        all_products.push(product);
                     }
-
-                    let product = Product {
-                        name,
-                        price: format!("Rp{}", price), // Add currency prefix
-                        shop: shop_id.clone(), // Placeholder, actual shop name can be set later
-                        location,
-                        photo,
-                        link: full_link,
-                    };
-                    
-                    // Add to collections
-                    all_products.push(product.clone());
-                    first_products_map
-                        .entry(shop_id.clone())
-                        .or_insert_with(Vec::new)
-                        .push(product);
-                        
-                    extracted_count += 1;
                 }
             }
-            
-            if extracted_count >= limit {
+
+            if all_products.len() >= limit {
                 break;
             }
-            
+
             // Go to next page
             current_page += 1;
             let next_url = format!(
-                "https://shopee.co.id/search?keyword={}&page={}",
-                urlencoding::encode(first_query),
-                current_page
+                "https://shopee.co.id/search?keyword={}&page={}{}",
+                urlencoding::encode(&seed_query.text),
+                current_page,
+                filter_params
             );
-            
-            // Navigate
-             let _ = driver.goto(&next_url).await;
-             tokio::time::sleep(std::time::Duration::from_secs(3)).await;
-            
-            // Check emptiness
-            if driver.find(By::Css(".shopee-search-item-result__item")).await.is_err() {
-                 break;
+
+            // Navigate, then check emptiness
+            match open_page(driver, &next_url, &items_or_empty, waits).await {
+                Ok(0) => {
+                    stable_count(driver, &item, waits).await;
+                }
+                Ok(_) => break,
+                Err(e) => {
+                    // Keep the shops found on earlier pages
+                    warning("shopee", progress, e);
+                    break;
+                }
             }
         }
 
-        let mut grouped: Vec<ShopResults> = Vec::new();
+        all_products
+    }
 
-        for slug in shop_slugs.into_iter() {
-            let shop_url = format!("https://shopee.co.id/?shop={}", slug); // This might need adjustment since Shopee shop URLs are structured differently
-            let shop_display = shop_names
-                .get(&slug)
-                .cloned()
-                .unwrap_or_else(|| slug.clone());
+    /// Searches `queries` in shops of an earlier run and returns the shops with the new
+    /// results added. A query a shop already has results for isn't searched again.
+    pub(crate) async fn extend<B: Browser>(
+        driver: &B,
+        shops: Vec<ShopResults>,
+        queries: &[SearchQuery],
+        progress: &dyn ProgressSink,
+        limit: usize,
+        options: &ScrapeOptions,
+        sel: &Selectors,
+    ) -> Result<Vec<ShopResults>, String> {
+        info!("Extending {} Shopee shop(s) with {} query(s)", shops.len(), queries.len());
+        let tracker = ProgressTracker::new("shopee", progress);
+        let waits = Waits::from_options(options);
+        let context = ShopContext {
+            tracker: &tracker,
+            progress,
+            queries,
+            limit,
+            waits: &waits,
+            sel,
+            cache: options.cache.as_deref(),
+        };
 
-            let mut qresults: Vec<QueryResult> = Vec::new();
+        let total = shops.len();
+        let mut extended: Vec<ShopResults> = Vec::new();
+        for (shop_index, mut shop) in shops.into_iter().enumerate() {
+            let missing = missing_queries(&shop, queries);
+            if missing.is_empty() {
+                extended.push(shop);
+                continue;
+            }
+            let Some(shop_id) = extract::shopee_shop_id_from_url(&shop.shop_url) else {
+                warning("shopee", progress, format!("No shop id in {}", shop.shop_url));
+                extended.push(shop);
+                continue;
+            };
 
-           
-            for q in queries.iter() {
-                let mut products: Vec<Product> = Vec::new();
+            progress.emit(ProgressEvent::ShopStarted {
+                platform: "shopee".to_string(),
+                shop: shop.shop_name.clone(),
+                index: shop_index + 1,
+                total,
+            });
+            for (query_index, q) in missing.iter().enumerate() {
+                tracker.searching(shop_index + 1, total, query_index + 1, missing.len());
+                let result = Self::shop_query(driver, &context, &shop_id, q).await;
+
+                progress.emit(ProgressEvent::QueryFinished {
+                    platform: "shopee".to_string(),
+                    shop: shop.shop_name.clone(),
+                    query: q.text.clone(),
+                    products: result.products.len(),
+                });
+                tracker.query_finished(result.products.len());
+                shop.results.push(result);
+            }
 
-                
+            tracker.shop_finished();
+            progress.emit(ProgressEvent::ShopFinished { shop: Box::new(shop.clone()) });
+            extended.push(shop);
+        }
 
-                // First query products don't need to be searched again in the shop
-                if q == first_query {
-                    if let Some(v) = first_products_map.get(&slug) {
-                        products = v.clone();
-                    }
-                } else {
-                    // For Shopee, we need to go back to the search page and search for the new query
-                    let search_url = format!(
-                        "https://shopee.co.id/search?keyword={}&shop={}",
-                        urlencoding::encode(&q),
-                        slug
-                    );
-                    let _ = driver.goto(&search_url).await;
-                    info!("Shopee search URL: {}", search_url);
-
-                    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
-
-                    // Wait for products to load
-                    {
-                        use std::time::Duration as StdDuration;
-                        let start = std::time::Instant::now();
-                        let timeout = StdDuration::from_secs(6);
-                        loop {
-                            if driver
-                                .find(By::Css(".shopee-search-item-result__item"))
-                                .await
-                                .is_ok()
-                            {
-                                info!("Shopee products found");
-                                break;
-                            }
-
-                            if start.elapsed() >= timeout {
-                                // timed out waiting
-                                info!("Timed out waiting for Shopee products to load");
-                                break;
-                            }
-
-                            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
-                        }
-                    }
+        Ok(extended)
+    }
 
-                    // Get Shopee cards
-                    let cards = match driver
-                        .find_all(By::Css(".shopee-search-item-result__item"))
-                        .await
-                    {
-                        Ok(v) => v,
-                        Err(_) => Vec::new(),
-                    };
+    // One query in one shop: from the result cache, or through the search URL
+    // restricted to the shop
+    async fn shop_query<B: Browser>(
+        driver: &B,
+        context: &ShopContext<'_>,
+        shop_id: &str,
+        q: &SearchQuery,
+    ) -> QueryResult {
+        let ShopContext {
+            progress,
+            limit,
+            waits,
+            sel,
+            cache,
+            ..
+        } = *context;
+        if let Some(cached) = cache.and_then(|c| c.get("shopee", shop_id, q)) {
+            return cached;
+        }
+        let item = sel.css("search_item");
+        let empty = sel.css("search_empty");
+        let items_or_empty = [item.as_str(), empty.as_str()];
+
+        let search_url = format!(
+            "https://shopee.co.id/search?keyword={}&shop={}{}",
+            urlencoding::encode(&q.text),
+            shop_id,
+            q.filter.shopee_params()
+        );
+        info!("Shopee search URL: {}", search_url);
+
+        // Wait for products to load
+        let result = match open_page(driver, &search_url, &items_or_empty, waits).await {
+            Ok(found) => {
+                if found == 0 {
+                    info!("Shopee products found");
+                    stable_count(driver, &item, waits).await;
+                }
+                page_loaded(driver, "shopee", progress).await;
 
-                    for c in cards.into_iter().take(limit) { // limit here too logic
-                        let link_element = match c.find(By::Css("a.contents")).await {
-                            Ok(el) => el,
-                            Err(_) => continue, // Skip if no link found
-                        };
-
-                        let link = match link_element.attr("href").await {
-                            Ok(opt) => opt.unwrap_or_default(),
-                            Err(_) => String::new(),
-                        };
-
-                        if link.starts_with("/") && !link.contains("find_similar_products") {
-                            let full_link = format!("https://shopee.co.id{}", link);
-
-                            // Extract product name
-                            let name = match c.find(By::Css(".line-clamp-2.break-words")).await {
-                                Ok(el) => el.text().await.unwrap_or_default(),
-                                Err(_) => String::new(),
-                            };
-
-                            // Extract price
-                            let price = {
-                                let price_element = match c.find(By::Css("[data-testid=\"a11y-label\"] + div .truncate.text-base\\/5.font-medium")).await {
-                                    Ok(el) => el,
-                                    Err(_) => {
-                                        // Alternative selector for price
-                                        match c.find(By::Css(".text-shopee-primary .truncate.text-base\\/5.font-medium")).await {
-                                            Ok(el) => el,
-                                            Err(_) => {
-                                                // Try another approach
-                                                match c.find(By::Css(".flex-shrink.min-w-0.mr-1.truncate.text-shopee-primary .truncate.text-base\\/5.font-medium")).await {
-                                                    Ok(el) => el,
-                                                    Err(_) => {
-                                                        // If all else fails, return empty string
-                                                        continue; // Skip this item
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-                                };
-                                price_element.text().await.unwrap_or_default()
-                            };
-
-                            // Extract location
-                            let location = match c
-                                .find(By::Css(
-                                    ".text-shopee-black54.font-extralight.text-sp10 .align-middle",
-                                ))
-                                .await
-                            {
-                                Ok(el) => el.text().await.unwrap_or_default(),
-                                Err(_) => String::new(),
-                            };
-
-                            // Extract photo
-                            let photo = match c
-                                .find(By::Css(
-                                    "img.w-full",
-                                ))
-                                .await
-                            {
-                                Ok(el) => el.attr("src").await.unwrap_or(None).unwrap_or_default(),
-                                Err(_) => {
-                                    // Try to get the first image in the product card
-                                    match c.find(By::Css("img")).await {
-                                        Ok(el) => {
-                                            el.attr("src").await.unwrap_or(None).unwrap_or_default()
-                                        }
-                                        Err(_) => String::new(),
-                                    }
-                                }
-                            };
-
-                            // Get shop info from product detail page
-                            // let (shop_name, _shop_url) = Self::get_shop_info_from_product(driver, &full_link).await;
-
-                            products.push(Product {
-                                name,
-                                price: format!("Rp{}", price),
-                                shop: slug.clone(),
-                                location,
-                                photo,
-                                link: full_link,
-                            });
-                        }
+                let mut products: Vec<Product> = Vec::new();
+                for c in sel.find_all(driver, "search_item").await.into_iter().take(limit) {
+                    let Some(mut product) = read_card(&c, sel).await else {
+                        continue;
+                    };
+                    product.shop = shop_id.to_string();
+                    if q.filter.matches(&product) {
+                        products.push(product);
                     }
                 }
-
-                qresults.push(QueryResult {
-                    query: q.clone(),
-                    products,
-                });
+                QueryResult::new(q, products)
             }
-
-            if !qresults.is_empty() && !qresults[0].products.is_empty() {
-                let first_link = qresults[0].products[0].link.clone();
-                let (shop_name, new_shop_url) = Self::get_shop_info_from_product(driver, &first_link).await;
-
-                let shop_result = ShopResults {
-                    shop_name: shop_name.clone(),
-                    shop_url: new_shop_url.clone(),
-                    platform: "shopee".to_string(),
-                    results: qresults,
-                };
-
-                // Emit progress real-time
-                let _ = window
-                    .emit("scrape:progress", shop_result.clone())
-                    .map_err(|e| e.to_string());
-
-                grouped.push(shop_result);
+            Err(e) => {
+                warning("shopee", progress, format!("Failed to search {} in shop {}: {}", q.text, shop_id, e));
+                page_loaded(driver, "shopee", progress).await;
+                QueryResult::failed(q, e)
             }
+        };
+        if let Some(cache) = cache {
+            cache.put("shopee", shop_id, q, &result);
         }
-
-        // Emit done
-        let _ = window.emit("scrape:done", ()).map_err(|e| e.to_string());
-
-        Ok(grouped)
+        result
     }
 
-    /// Helper method to extract shop info from product detail page
-    /// Visits the product page and finds shop info in .page-product__shop element
-    async fn get_shop_info_from_product(driver: &WebDriver, product_url: &str) -> (String, String) {
-        // Navigate to product detail page
-        if let Err(_) = driver.goto(product_url).await {
-            info!("Failed to navigate to product page: {}", product_url);
-            return (String::new(), String::new());
+    /// Reads the shop name from a product page of the shop, for shops whose own page
+    /// doesn't show it.
+    async fn shop_name_from_product<B: Browser>(
+        driver: &B,
+        sel: &Selectors,
+        product_url: &str,
+        waits: &Waits,
+    ) -> Option<String> {
+        if let Err(e) = open_page(driver, product_url, &[&sel.css("product_shop")], waits).await {
+            info!("Failed to navigate to product page: {}", e);
+            return None;
         }
 
-        // Wait a bit for page to load
-        tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
-
-        // Try to find the shop element
-        let shop_element = match driver.find(By::Css(".page-product__shop")).await {
-            Ok(el) => el,
-            Err(_) => {
-                info!("Could not find .page-product__shop element on page: {}", product_url);
-                return (String::new(), String::new());
-            }
-        };
-
-        // Find the <a> tag inside the shop element
-        let shop_link = match shop_element.find(By::Css("a")).await {
-            Ok(el) => el,
-            Err(_) => {
-                info!("Could not find <a> tag inside .page-product__shop");
-                return (String::new(), String::new());
-            }
+        let Some(shop_element) = sel.find(driver, "product_shop").await else {
+            info!("Could not find shop element on page: {}", product_url);
+            return None;
         };
 
         // Extract shop name (div sibiling tag a -> div -> text)
-        let mut shop_name = match shop_element.find(By::XPath(".//a/following-sibling::div//div")).await {
-            Ok(el) => el.text().await.unwrap_or_default(),
-            Err(_) => String::new(),
-        };
+        let mut shop_name = sel.text(&shop_element, "product_shop_name").await;
 
         if shop_name.is_empty() {
-            let candidates = match shop_element
-                .find_all(By::XPath(".//a/following-sibling::div//div"))
-                .await
-            {
-                Ok(list) => list,
-                Err(_) => vec![],
-            };
+            let candidates = sel.find_all(&shop_element, "product_shop_name").await;
 
             for el in candidates {
-                let text = el.text().await.unwrap_or_default();
+                let text = el.text().await;
 
                 if !text.trim().is_empty()
                     && !text.to_lowercase().contains("aktif")
@@ -989,33 +1591,47 @@ impl ShopeeScraper {
             }
         }
 
-        // Extract shop URL (href attribute)
-        let shop_url = match shop_link.attr("href").await {
-            Ok(Some(href)) => {
-                if href.starts_with("/") {
-                    format!("https://shopee.co.id{}", href)
-                } else if href.starts_with("http") {
-                    href
-                } else {
-                    format!("https://shopee.co.id/{}", href)
-                }
+        info!("Shop name from product page {}: {}", product_url, shop_name);
+        Some(shop_name.trim().to_string()).filter(|name| !name.is_empty())
+    }
+
+    /// Visits the shop page and reads the seller overview ("Penilaian", "Performa Chat",
+    /// "Bergabung", ...) plus Mall/Star badges, and the shop name when shown.
+    async fn scrape_shop_profile<B: Browser>(
+        driver: &B,
+        sel: &Selectors,
+        shop_url: &str,
+        waits: &Waits,
+    ) -> (ShopProfile, Option<String>) {
+        let header = match open_page(driver, shop_url, &[&sel.css("shop_profile")], waits).await {
+            Ok(_) => match sel.find(driver, "shop_profile").await {
+                Some(el) => el,
+                None => return (ShopProfile::default(), None),
+            },
+            Err(e) => {
+                info!("No Shopee shop profile: {}", e);
+                return (ShopProfile::default(), None);
             }
-            _ => String::new(),
         };
 
-        info!("Extracted shop info - Name: {}, URL: {}", shop_name, shop_url);
-        
-        (shop_name, shop_url)
+        let name = sel
+            .read(driver, "shop_name")
+            .await
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty());
+        let text = header.text().await;
+        let labels = image_labels(&header, sel).await;
+        (parse_shop_profile(&text, &labels), name)
     }
 
-    async fn perform_site_search(driver: &WebDriver, query: &str) -> Result<(), ()> {
+    async fn perform_site_search<B: Browser>(driver: &B, sel: &Selectors, query: &str) -> Result<(), ()> {
         // Cari input pada Shopee
-        let sel = r#"input[type=\"text\"][class*=\"shopee-search-input__input\"]"#;
-        if let Ok(el) = driver.find(By::Css(sel)).await {
-            if el.is_displayed().await.unwrap_or(false) {
+        if let Some(el) = sel.find(driver, "search_input").await {
+            if el.is_displayed().await {
                 let _ = el.click().await;
-                let _ = el.clear().await;
+                el.clear().await;
                 let _ = el.send_keys(query).await;
+                driver.expect_navigation().await;
                 // Submit using enter key
                 let _ = el.send_keys("\n").await;
                 return Ok(());
@@ -1024,3 +1640,306 @@ impl ShopeeScraper {
         Err(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake_browser::FakeBrowser;
+    use std::sync::Arc;
+
+    const TOKOPEDIA_SEARCH: &str = include_str!("../tests/fixtures/tokopedia/search.html");
+    const SHOPEE_SEARCH: &str = include_str!("../tests/fixtures/shopee/search.html");
+    const SHOPEE_SHOP: &str = include_str!("../tests/fixtures/shopee/shop.html");
+
+    // Second result page: one more product of the first shop
+    const SHOPEE_PAGE_2: &str = r#"<ul><li class="shopee-search-item-result__item">
+        <a class="contents" href="/Kabel-NYM-3x2.5-Eterna-50m-i.124455053.29705222999">
+          <div class="line-clamp-2 break-words">Kabel NYM 3x2.5 Eterna 50m</div>
+          <div class="text-shopee-primary"><span class="truncate text-base/5 font-medium">890.000</span></div>
+        </a>
+    </li></ul>"#;
+    const SHOPEE_NO_RESULTS: &str =
+        r#"<div class="shopee-search-empty-result-section">Hasil tidak ditemukan</div>"#;
+
+    // A missing element costs a second instead of the default ten
+    fn options() -> ScrapeOptions {
+        ScrapeOptions {
+            wait_timeout_secs: 1,
+            settle_ms: 0,
+            max_retries: 0,
+            max_tabs: 1,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn tokopedia_searches_by_url_without_search_box() {
+        let sel = Selectors::builtin("tokopedia");
+        let search = "https://www.tokopedia.com/search?q=kabel%20nym";
+        // The home page never shows the search box
+        let browser = FakeBrowser::default().page(search, TOKOPEDIA_SEARCH);
+        let progress = |_: ProgressEvent| {};
+        let tracker = ProgressTracker::new("tokopedia", &progress);
+        let waits = Waits::from_options(&options());
+
+        let queries = [SearchQuery::from("kabel nym")];
+        let shops = TokopediaScraper::collect_shops(&browser, &queries, &Seeds::new(vec![0]), 10, &sel, &waits, &tracker, &progress)
+            .await
+            .expect("seed search loads");
+
+        assert_eq!(browser.visited(), ["https://www.tokopedia.com/", search]);
+        let mut slugs: Vec<&str> = shops.iter().map(|s| s.slug.as_str()).collect();
+        slugs.sort();
+        assert_eq!(slugs, ["listrikmurah", "supremeofficial", "tokolistrikjaya"]);
+        assert!(shops.iter().all(|s| s.total == 3 && s.seed_products[&0].len() == 1));
+        let jaya = shops.iter().find(|s| s.slug == "tokolistrikjaya").unwrap();
+        assert_eq!(jaya.shop_display, "Toko Listrik Jaya");
+    }
+
+    #[tokio::test]
+    async fn typed_search_does_not_read_the_page_it_was_typed_on() {
+        let sel = Selectors::builtin("tokopedia");
+        let search = "https://www.tokopedia.com/search?q=kabel%20nym";
+        // The search box sits above results of an earlier search; typing never submits
+        // here, so the results must come from the search URL
+        let home = r#"<input data-unify="Search" type="search">
+            <div data-ssr="contentProductsSRPSSR">
+              <a href="https://www.tokopedia.com/tokolama/lampu-led-10w"><span>Lampu LED 10W</span></a>
+            </div>"#;
+        let browser = FakeBrowser::default()
+            .page("https://www.tokopedia.com/", home)
+            .page(search, TOKOPEDIA_SEARCH);
+        let progress = |_: ProgressEvent| {};
+        let tracker = ProgressTracker::new("tokopedia", &progress);
+        let waits = Waits::from_options(&options());
+
+        let queries = [SearchQuery::from("kabel nym")];
+        let shops = TokopediaScraper::collect_shops(&browser, &queries, &Seeds::new(vec![0]), 10, &sel, &waits, &tracker, &progress)
+            .await
+            .expect("seed search loads");
+
+        assert_eq!(browser.visited(), ["https://www.tokopedia.com/", search]);
+        assert_eq!(shops.len(), 3);
+        assert!(shops.iter().all(|s| s.slug != "tokolama"));
+    }
+
+    #[tokio::test]
+    async fn tokopedia_fails_without_seed_results() {
+        let sel = Selectors::builtin("tokopedia");
+        let browser = FakeBrowser::default();
+        let progress = |_: ProgressEvent| {};
+        let tracker = ProgressTracker::new("tokopedia", &progress);
+        let waits = Waits::from_options(&options());
+
+        let queries = [SearchQuery::from("kabel nym")];
+        let result = TokopediaScraper::collect_shops(&browser, &queries, &Seeds::new(vec![0]), 10, &sel, &waits, &tracker, &progress).await;
+        let error = result.err().expect("no shops without a seed search");
+        assert!(error.starts_with("Tokopedia search did not load"), "{}", error);
+    }
+
+    #[test]
+    fn union_seeds_keep_each_querys_products() {
+        let queries = [
+            SearchQuery::from("kabel nym"),
+            SearchQuery::from("fitting lampu"),
+            SearchQuery::from("stop kontak"),
+        ];
+        let progress = |_: ProgressEvent| {};
+        let options = ScrapeOptions {
+            seed: SeedMode::Union { indices: vec![2, 0, 2, 7] },
+            ..Default::default()
+        };
+        assert_eq!(seed_indices(&options, &queries, "tokopedia", &progress), [2, 0]);
+        let exact = |total| Some(ResultCount { total, at_least: false });
+        assert_eq!(rarest(&queries, &[exact(120), None, exact(8)], "tokopedia", &progress), 2);
+        assert_eq!(rarest(&queries, &[None, None, None], "tokopedia", &progress), 0);
+
+        let product = |shop: &str, name: &str| Product {
+            name: name.to_string(),
+            shop: shop.to_string(),
+            link: format!("https://www.tokopedia.com/{}/{}", shop, name),
+            ..Default::default()
+        };
+        let seeds = vec![
+            (2, vec![product("tokolistrikjaya", "stop-kontak-broco")]),
+            (0, vec![product("listrikmurah", "kabel-nym"), product("tokolistrikjaya", "kabel-nym")]),
+        ];
+        let shops = group_by_shop("tokopedia", seeds, &queries);
+        let slugs: Vec<&str> = shops.iter().map(|s| s.slug.as_str()).collect();
+        assert_eq!(slugs, ["tokolistrikjaya", "listrikmurah"]);
+        let jaya = &shops[0];
+        assert_eq!(jaya.seed_products[&2].len(), 1);
+        assert_eq!(jaya.seed_products[&0].len(), 1);
+        assert!(!shops[1].seed_products.contains_key(&2));
+    }
+
+    #[tokio::test]
+    async fn shopee_pages_by_url_and_groups_by_shop_id() {
+        let sel = Selectors::builtin("shopee");
+        let search = "https://shopee.co.id/search?keyword=kabel%20nym";
+        let page_2 = format!("{}&page=1", search);
+        let page_3 = format!("{}&page=2", search);
+        // The next button is only found through XPath, which the fake browser doesn't do
+        let browser = FakeBrowser::default()
+            .page(search, SHOPEE_SEARCH)
+            .page(&page_2, SHOPEE_PAGE_2)
+            .page(&page_3, SHOPEE_NO_RESULTS)
+            .page("https://shopee.co.id/shop/124455053", SHOPEE_SHOP);
+        let progress = |_: ProgressEvent| {};
+        // Named by an earlier run; its shop page doesn't load this time
+        let mut names = ShopNames::default();
+        names.insert("88776655", "Supreme Kabel Official");
+
+        let queries = [SearchQuery::from("kabel nym")];
+        let shops = ShopeeScraper::scrape(&browser, &queries, &progress, 10, &options(), &sel, &mut names)
+            .await
+            .expect("seed search loads");
+
+        let visited = browser.visited();
+        assert!(visited.contains(&page_2), "{:?}", visited);
+        assert!(visited.contains(&page_3), "{:?}", visited);
+        // Names come from shop pages and the cache, not product pages
+        assert!(!visited.iter().any(|url| url.contains("-i.")), "{:?}", visited);
+
+        // Products of both result pages are grouped by the shop id in their links
+        assert_eq!(shops.len(), 2);
+        let jaya = shops
+            .iter()
+            .find(|s| s.shop_url == "https://shopee.co.id/shop/124455053")
+            .expect("first shop");
+        assert_eq!(jaya.shop_name, "Toko Listrik Jaya Official");
+        assert_eq!(jaya.results[0].products.len(), 2);
+        assert!(jaya.results[0].products.iter().all(|p| p.shop == "124455053"));
+        assert_eq!(jaya.profile.as_ref().and_then(|p| p.rating), Some(4.9));
+        assert_eq!(names.get("124455053"), Some("Toko Listrik Jaya Official"));
+
+        let supreme = shops
+            .iter()
+            .find(|s| s.shop_url == "https://shopee.co.id/shop/88776655")
+            .expect("second shop");
+        assert_eq!(supreme.shop_name, "Supreme Kabel Official");
+    }
+
+    #[tokio::test]
+    async fn shopee_rarest_seed_goes_on_from_the_counted_page() {
+        let sel = Selectors::builtin("shopee");
+        let kabel = "https://shopee.co.id/search?keyword=kabel%20nym";
+        let fitting = "https://shopee.co.id/search?keyword=fitting%20lampu";
+        // Two pages of kabel results; one card of fitting, without a page count
+        let browser = FakeBrowser::default()
+            .page(kabel, SHOPEE_SEARCH)
+            .page(fitting, SHOPEE_PAGE_2)
+            .page(&format!("{}&page=1", fitting), SHOPEE_NO_RESULTS);
+        let warnings = std::sync::Mutex::new(Vec::new());
+        let progress = |event: ProgressEvent| {
+            if let ProgressEvent::Warning { message, .. } = event {
+                warnings.lock().unwrap().push(message);
+            }
+        };
+        let options = ScrapeOptions {
+            seed: SeedMode::Rarest,
+            ..options()
+        };
+        let mut names = ShopNames::default();
+
+        let queries = [SearchQuery::from("kabel nym"), SearchQuery::from("fitting lampu")];
+        let shops = ShopeeScraper::scrape(&browser, &queries, &progress, 10, &options, &sel, &mut names)
+            .await
+            .expect("seed search loads");
+
+        let visited = browser.visited();
+        assert_eq!(visited[..2], [kabel, fitting]);
+        assert_eq!(visited.iter().filter(|url| url.as_str() == fitting).count(), 1, "{:?}", visited);
+        assert_eq!(shops.len(), 1);
+        assert_eq!(shops[0].results[1].products.len(), 1);
+        // One card without a page count is only a lower bound
+        let warnings = warnings.lock().unwrap();
+        assert!(warnings.iter().any(|w| w.contains("fitting lampu may not be the rarest")), "{:?}", warnings);
+    }
+
+    #[tokio::test]
+    async fn shopee_does_not_remember_shop_ids_as_names() {
+        let sel = Selectors::builtin("shopee");
+        let search = "https://shopee.co.id/search?keyword=kabel%20nym";
+        // Neither the shop page nor the product page loads
+        let browser = FakeBrowser::default()
+            .page(search, SHOPEE_PAGE_2)
+            .page(&format!("{}&page=1", search), SHOPEE_NO_RESULTS);
+        let progress = |_: ProgressEvent| {};
+        let mut names = ShopNames::default();
+
+        let queries = [SearchQuery::from("kabel nym")];
+        let shops = ShopeeScraper::scrape(&browser, &queries, &progress, 10, &options(), &sel, &mut names)
+            .await
+            .expect("seed search loads");
+
+        assert_eq!(shops.len(), 1);
+        assert_eq!(shops[0].shop_name, "124455053");
+        assert_eq!(names.get("124455053"), None);
+    }
+
+    #[tokio::test]
+    async fn shopee_extend_searches_only_missing_queries() {
+        let sel = Selectors::builtin("shopee");
+        let in_shop = "https://shopee.co.id/search?keyword=fitting%20lampu&shop=124455053";
+        let browser = FakeBrowser::default().page(in_shop, SHOPEE_PAGE_2);
+        let progress = |_: ProgressEvent| {};
+        let cache = Arc::new(ResultCache::new(3600, false));
+        let options = ScrapeOptions {
+            cache: Some(Arc::clone(&cache)),
+            ..options()
+        };
+
+        let kabel = SearchQuery::from("kabel nym");
+        let earlier = |shop_url: &str| ShopResults {
+            shop_name: "Toko Listrik Jaya Official".to_string(),
+            shop_url: shop_url.to_string(),
+            platform: "shopee".to_string(),
+            results: vec![QueryResult::new(&kabel, Vec::new())],
+            profile: None,
+            basket: None,
+        };
+        // The second shop was saved before shops had their id in the URL
+        let shops = vec![
+            earlier("https://shopee.co.id/shop/124455053"),
+            earlier("https://shopee.co.id/tokolistrikjaya"),
+        ];
+
+        let fitting = SearchQuery::from("fitting lampu");
+        let queries = [SearchQuery::from("Kabel  NYM"), fitting.clone()];
+        let shops = ShopeeScraper::extend(&browser, shops, &queries, &progress, 10, &options, &sel)
+            .await
+            .expect("extend never fails");
+
+        assert_eq!(browser.visited(), [in_shop]);
+        assert_eq!(shops.len(), 2);
+        let results = &shops[0].results;
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[1].query, "fitting lampu");
+        assert_eq!(results[1].products.len(), 1);
+        assert_eq!(results[1].products[0].shop, "124455053");
+        assert_eq!(shops[1].results.len(), 1);
+        assert!(cache.get("shopee", "124455053", &fitting).is_some());
+    }
+
+    #[tokio::test]
+    async fn product_stock_is_read_from_the_stock_label_only() {
+        let sel = Selectors::builtin("tokopedia");
+        let waits = Waits::from_options(&options());
+        let labelled = "https://www.tokopedia.com/tokolistrikjaya/kabel-nym";
+        let unlabelled = "https://www.tokopedia.com/tokolistrikjaya/fitting-lampu";
+        let browser = FakeBrowser::default()
+            .page(labelled, include_str!("../tests/fixtures/tokopedia/product.html"))
+            .page(
+                unlabelled,
+                r#"<h1 data-testid="lblPDPDetailProductName">Fitting Lampu E27</h1>
+                <div data-testid="lblPDPDescriptionProduk">Stok tersisa sedikit, buruan!</div>
+                <p>Ulasan: sisa 2 minggu baru sampai</p>"#,
+            );
+
+        let detail = scrape_product_detail(&browser, &sel, labelled, &waits).await.unwrap();
+        assert_eq!(detail.stock, Some(25));
+        let detail = scrape_product_detail(&browser, &sel, unlabelled, &waits).await.unwrap();
+        assert_eq!(detail.stock, None);
+    }
+}
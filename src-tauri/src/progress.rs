@@ -0,0 +1,228 @@
+// Scrape progress reporting, independent of where it is shown (app window, CLI, tests)
+
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::browser::Browser;
+use crate::models::{PlatformReport, ShopResults};
+
+/// Everything a running scrape reports. Serialized as `{ "type": "shop_started", ... }`.
+#[derive(Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    /// A search or shop page finished loading.
+    PageLoaded { platform: String, url: String },
+    /// Searching the remaining queries in shop `index` (1-based) of `total`.
+    ShopStarted {
+        platform: String,
+        shop: String,
+        index: usize,
+        total: usize,
+    },
+    QueryFinished {
+        platform: String,
+        shop: String,
+        query: String,
+        products: usize,
+    },
+    ShopFinished { shop: Box<ShopResults> },
+    /// A platform is done, successfully or not; the run goes on with the next one.
+    PlatformFinished { report: PlatformReport },
+    /// Something went wrong but the scrape goes on (failed navigation, missing shop info).
+    Warning { platform: String, message: String },
+    /// Where the run is, sent on every scroll, result page and in-shop search.
+    Status(ScrapeStatus),
+    /// All platforms of the run combined, sent along with every `Status`.
+    Overall(OverallStatus),
+    /// The whole run is over, successful or not.
+    Done,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(tag = "phase", rename_all = "snake_case")]
+pub enum Phase {
+    /// Scrolling / paging the seed query results to discover shops, until
+    /// `target_products` cards are found or `max_steps` (when known) runs out.
+    CollectingShops {
+        step: usize,
+        max_steps: Option<usize>,
+        target_products: usize,
+    },
+    /// Query `query` of `queries` in shop `shop` of `shops` (all 1-based).
+    SearchingShop {
+        shop: usize,
+        shops: usize,
+        query: usize,
+        queries: usize,
+    },
+}
+
+#[derive(Serialize, Clone)]
+pub struct ScrapeStatus {
+    pub platform: String,
+    #[serde(flatten)]
+    pub phase: Phase,
+    // Seed products collected so far (collecting) or products found in shops (searching)
+    pub products_found: usize,
+    pub shops_done: usize,
+    pub elapsed_secs: u64,
+    // None until at least one in-shop search has been timed
+    pub eta_secs: Option<u64>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct OverallStatus {
+    // Platforms that haven't finished yet
+    pub running: Vec<String>,
+    pub shops_done: usize,
+    pub products_found: usize,
+    pub elapsed_secs: u64,
+    // The slowest running platform's estimate
+    pub eta_secs: Option<u64>,
+}
+
+pub trait ProgressSink: Send + Sync {
+    fn emit(&self, event: ProgressEvent);
+}
+
+// Closures, e.g. the CLI printing to stderr
+impl<F> ProgressSink for F
+where
+    F: Fn(ProgressEvent) + Send + Sync,
+{
+    fn emit(&self, event: ProgressEvent) {
+        self(event)
+    }
+}
+
+// Channel-based event stream; a closed receiver just drops events
+impl ProgressSink for UnboundedSender<ProgressEvent> {
+    fn emit(&self, event: ProgressEvent) {
+        let _ = self.send(event);
+    }
+}
+
+/// Reports the page the browser is currently on.
+pub(crate) async fn page_loaded<B: Browser>(browser: &B, platform: &str, progress: &dyn ProgressSink) {
+    let url = browser.current_url().await;
+    progress.emit(ProgressEvent::PageLoaded {
+        platform: platform.to_string(),
+        url,
+    });
+}
+
+pub fn warning(platform: &str, progress: &dyn ProgressSink, message: String) {
+    log::warn!("[{}] {}", platform, message);
+    progress.emit(ProgressEvent::Warning {
+        platform: platform.to_string(),
+        message,
+    });
+}
+
+struct TrackerState {
+    shops_done: usize,
+    products_found: usize,
+    last_step: Option<Instant>,
+    // Time between consecutive `searching` calls: one query in one shop, including
+    // the shop's profile / info pages visited around it
+    step_times: Vec<Duration>,
+}
+
+/// Turns scraper milestones into `ProgressEvent::Status`, estimating the remaining
+/// time from the average duration of the shop steps seen so far.
+pub struct ProgressTracker<'a> {
+    platform: &'static str,
+    progress: &'a dyn ProgressSink,
+    started: Instant,
+    state: Mutex<TrackerState>,
+}
+
+impl<'a> ProgressTracker<'a> {
+    pub fn new(platform: &'static str, progress: &'a dyn ProgressSink) -> Self {
+        ProgressTracker {
+            platform,
+            progress,
+            started: Instant::now(),
+            state: Mutex::new(TrackerState {
+                shops_done: 0,
+                products_found: 0,
+                last_step: None,
+                step_times: Vec::new(),
+            }),
+        }
+    }
+
+    pub fn collecting(
+        &self,
+        step: usize,
+        max_steps: Option<usize>,
+        target_products: usize,
+        products_found: usize,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        state.products_found = products_found;
+        self.send(
+            &state,
+            Phase::CollectingShops {
+                step,
+                max_steps,
+                target_products,
+            },
+            None,
+        );
+    }
+
+    /// Call when work on query `query` (1-based) of shop `shop` starts, before any
+    /// navigation for it. Each shop must report every query so steps stay comparable.
+    pub fn searching(&self, shop: usize, shops: usize, query: usize, queries: usize) {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        if let Some(last) = state.last_step.replace(now) {
+            state.step_times.push(now - last);
+        }
+        if shop == 1 && query == 1 {
+            // Seed products don't count towards shop results
+            state.products_found = 0;
+        }
+
+        let remaining_steps = (shops - shop) * queries + (queries - query + 1);
+        let eta = if state.step_times.is_empty() {
+            None
+        } else {
+            let average = state.step_times.iter().sum::<Duration>() / state.step_times.len() as u32;
+            Some((average * remaining_steps as u32).as_secs())
+        };
+
+        self.send(
+            &state,
+            Phase::SearchingShop {
+                shop,
+                shops,
+                query,
+                queries,
+            },
+            eta,
+        );
+    }
+
+    pub fn query_finished(&self, products: usize) {
+        self.state.lock().unwrap().products_found += products;
+    }
+
+    pub fn shop_finished(&self) {
+        self.state.lock().unwrap().shops_done += 1;
+    }
+
+    fn send(&self, state: &TrackerState, phase: Phase, eta_secs: Option<u64>) {
+        self.progress.emit(ProgressEvent::Status(ScrapeStatus {
+            platform: self.platform.to_string(),
+            phase,
+            products_found: state.products_found,
+            shops_done: state.shops_done,
+            elapsed_secs: self.started.elapsed().as_secs(),
+            eta_secs,
+        }));
+    }
+}
@@ -0,0 +1,204 @@
+// Shop search results of earlier runs, kept in `<config dir>/satu-toko/result_cache.json`
+// for `ScrapeOptions::cache_ttl_secs`. Re-running an edited shopping list then only
+// searches the shops and queries that are new, or whose results have expired.
+//
+// Entries are keyed by platform, shop and query text (case and spacing ignored), and
+// remember the query's filter: results searched with a different filter aren't
+// reused, since the filter also narrows what the marketplace returns.
+
+use chrono::Utc;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::models::{Product, QueryFilter, QueryResult, QueryStatus, SearchQuery};
+
+fn cache_file() -> Option<PathBuf> {
+    let config_dir = dirs::config_dir()?;
+    Some(config_dir.join("satu-toko").join("result_cache.json"))
+}
+
+/// Query text as a cache key: "Kabel  NYM" and "kabel nym" are the same search.
+pub fn normalize_query(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn key(platform: &str, shop: &str, text: &str) -> String {
+    format!("{} {} {}", platform, shop, normalize_query(text))
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Entry {
+    // Unix seconds
+    searched_at: i64,
+    filter: QueryFilter,
+    products: Vec<Product>,
+}
+
+#[derive(Default)]
+struct Entries {
+    map: BTreeMap<String, Entry>,
+    changed: bool,
+}
+
+/// Cached results of one run. `new` gives one that is never written to disk.
+pub struct ResultCache {
+    path: Option<PathBuf>,
+    ttl_secs: u64,
+    // Lookups miss, but new results are still stored
+    refresh: bool,
+    entries: Mutex<Entries>,
+}
+
+// Products aren't `Debug`; the options printing the cache only need its settings
+impl fmt::Debug for ResultCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResultCache")
+            .field("path", &self.path)
+            .field("ttl_secs", &self.ttl_secs)
+            .field("refresh", &self.refresh)
+            .finish()
+    }
+}
+
+impl ResultCache {
+    pub fn new(ttl_secs: u64, refresh: bool) -> Self {
+        ResultCache {
+            path: None,
+            ttl_secs,
+            refresh,
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+
+    /// The results earlier runs left in the config directory.
+    pub fn load(ttl_secs: u64, refresh: bool) -> Self {
+        let mut cache = ResultCache::new(ttl_secs, refresh);
+        let Some(path) = cache_file() else {
+            return cache;
+        };
+        if let Ok(content) = fs::read_to_string(&path) {
+            match serde_json::from_str(&content) {
+                Ok(map) => cache.entries.get_mut().unwrap().map = map,
+                Err(e) => warn!("Ignoring unreadable result cache {}: {}", path.display(), e),
+            }
+        }
+        cache.path = Some(path);
+        cache
+    }
+
+    /// The products an earlier run found for `query` in `shop`, if still fresh and
+    /// searched with the same filter.
+    pub fn get(&self, platform: &str, shop: &str, query: &SearchQuery) -> Option<QueryResult> {
+        if self.refresh {
+            return None;
+        }
+        let entries = self.entries.lock().unwrap();
+        let entry = entries.map.get(&key(platform, shop, &query.text))?;
+        let age = Utc::now().timestamp().saturating_sub(entry.searched_at).max(0) as u64;
+        if age >= self.ttl_secs || entry.filter != query.filter {
+            return None;
+        }
+        Some(QueryResult::cached(query, entry.products.clone(), age))
+    }
+
+    /// Remembers a search that loaded; failed searches are searched again next time.
+    pub fn put(&self, platform: &str, shop: &str, query: &SearchQuery, result: &QueryResult) {
+        if result.status == QueryStatus::Failed || result.cached_age_secs.is_some() {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        entries.map.insert(
+            key(platform, shop, &query.text),
+            Entry {
+                searched_at: Utc::now().timestamp(),
+                filter: query.filter.clone(),
+                products: result.products.clone(),
+            },
+        );
+        entries.changed = true;
+    }
+
+    /// Writes the cache back without the expired entries, if the run added any.
+    pub fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut entries = self.entries.lock().unwrap();
+        if !entries.changed {
+            return Ok(());
+        }
+        let now = Utc::now().timestamp();
+        let ttl = self.ttl_secs as i64;
+        entries.map.retain(|_, entry| now - entry.searched_at < ttl);
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create config directory: {}", e))?;
+        }
+        let content = serde_json::to_string(&entries.map)
+            .map_err(|e| format!("Failed to serialize result cache: {}", e))?;
+        fs::write(path, content).map_err(|e| format!("Failed to write result cache: {}", e))?;
+        entries.changed = false;
+        info!("Result cache saved: {} entries", entries.map.len());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn product(name: &str) -> Product {
+        Product {
+            name: name.to_string(),
+            price: "Rp10.000".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn reuses_fresh_results_of_the_same_search() {
+        let cache = ResultCache::new(3600, false);
+        let query = SearchQuery::from("Fitting  Lampu");
+        let result = QueryResult::new(&query, vec![product("Fitting E27")]);
+        cache.put("tokopedia", "tokolistrikjaya", &query, &result);
+
+        let same = SearchQuery::from("fitting lampu");
+        let cached = cache.get("tokopedia", "tokolistrikjaya", &same).expect("cached");
+        assert_eq!(cached.products.len(), 1);
+        assert_eq!(cached.status, QueryStatus::Ok);
+        assert!(cached.cached_age_secs.is_some());
+
+        // Another shop, platform or filter is another search
+        assert!(cache.get("tokopedia", "listrikmurah", &same).is_none());
+        assert!(cache.get("shopee", "tokolistrikjaya", &same).is_none());
+        let mut filtered = same.clone();
+        filtered.filter.max_price = Some(5000);
+        assert!(cache.get("tokopedia", "tokolistrikjaya", &filtered).is_none());
+    }
+
+    #[test]
+    fn skips_failed_expired_and_refreshed_results() {
+        let query = SearchQuery::from("kabel nym");
+        let cache = ResultCache::new(3600, false);
+        cache.put("shopee", "88776655", &query, &QueryResult::failed(&query, "timeout".to_string()));
+        assert!(cache.get("shopee", "88776655", &query).is_none());
+
+        let expired = ResultCache::new(0, false);
+        expired.put("shopee", "88776655", &query, &QueryResult::new(&query, Vec::new()));
+        assert!(expired.get("shopee", "88776655", &query).is_none());
+
+        let refresh = ResultCache::new(3600, true);
+        refresh.put("shopee", "88776655", &query, &QueryResult::new(&query, Vec::new()));
+        assert!(refresh.get("shopee", "88776655", &query).is_none());
+        assert_eq!(refresh.entries.lock().unwrap().map.len(), 1);
+    }
+}
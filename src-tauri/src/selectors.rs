@@ -0,0 +1,265 @@
+// Selector definitions per platform
+//
+// Every CSS/XPath selector the scrapers use lives in `selectors/<platform>.json`,
+// compiled in as the defaults. A file with the same name in
+// `<config dir>/satu-toko/selectors/` overrides single keys without a new build, as
+// long as its `version` is not older than the shipped one: after a release updates
+// the defaults, an override written against the old markup is ignored.
+//
+// A key holds one rule or an ordered list of fallbacks, each either a CSS string or
+// `{ "css" | "xpath": ..., "attr": ... }` to read an attribute instead of the text.
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::browser::{Element, Locator, Scope};
+
+const TOKOPEDIA: &str = include_str!("../selectors/tokopedia.json");
+const SHOPEE: &str = include_str!("../selectors/shopee.json");
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "RuleInput")]
+pub struct Rule {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub css: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub xpath: Option<String>,
+    // Attribute to read; the element's text when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attr: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RuleInput {
+    Css(String),
+    Full {
+        css: Option<String>,
+        xpath: Option<String>,
+        attr: Option<String>,
+    },
+}
+
+impl From<RuleInput> for Rule {
+    fn from(input: RuleInput) -> Self {
+        match input {
+            RuleInput::Css(css) => Rule {
+                css: Some(css),
+                ..Default::default()
+            },
+            RuleInput::Full { css, xpath, attr } => Rule { css, xpath, attr },
+        }
+    }
+}
+
+impl Rule {
+    fn locator(&self) -> Option<Locator<'_>> {
+        match (&self.css, &self.xpath) {
+            (Some(css), _) => Some(Locator::Css(css)),
+            (None, Some(xpath)) => Some(Locator::XPath(xpath)),
+            (None, None) => None,
+        }
+    }
+}
+
+// Rules of one key, tried in order
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "ChainInput")]
+pub struct Chain(pub Vec<Rule>);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ChainInput {
+    Many(Vec<Rule>),
+    One(Rule),
+}
+
+impl From<ChainInput> for Chain {
+    fn from(input: ChainInput) -> Self {
+        match input {
+            ChainInput::Many(rules) => Chain(rules),
+            ChainInput::One(rule) => Chain(vec![rule]),
+        }
+    }
+}
+
+/// Contents of one definition file.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SelectorFile {
+    #[serde(default)]
+    pub platform: String,
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub selectors: HashMap<String, Chain>,
+}
+
+/// The selectors of one platform for a scrape run.
+#[derive(Clone, Debug)]
+pub struct Selectors {
+    pub platform: String,
+    pub version: u32,
+    // Path of the override merged over the defaults, if any
+    pub overridden_by: Option<PathBuf>,
+    rules: HashMap<String, Chain>,
+}
+
+fn override_file(platform: &str) -> Option<PathBuf> {
+    let config_dir = dirs::config_dir()?;
+    Some(
+        config_dir
+            .join("satu-toko")
+            .join("selectors")
+            .join(format!("{}.json", platform)),
+    )
+}
+
+impl Selectors {
+    /// The definitions shipped with the app.
+    pub fn builtin(platform: &str) -> Self {
+        let source = match platform {
+            "shopee" => SHOPEE,
+            _ => TOKOPEDIA,
+        };
+        let file: SelectorFile =
+            serde_json::from_str(source).expect("shipped selector file is valid JSON");
+        Selectors {
+            platform: platform.to_string(),
+            version: file.version,
+            overridden_by: None,
+            rules: file.selectors,
+        }
+    }
+
+    /// The shipped definitions with the config dir override merged in. Read at the
+    /// start of every run, so an edited override applies to the next run.
+    pub fn load(platform: &str) -> Self {
+        let mut selectors = Self::builtin(platform);
+        let Some(path) = override_file(platform) else {
+            return selectors;
+        };
+        if !path.exists() {
+            return selectors;
+        }
+
+        let file = match fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read selector override: {}", e))
+            .and_then(|content| {
+                serde_json::from_str::<SelectorFile>(&content)
+                    .map_err(|e| format!("Failed to parse selector override: {}", e))
+            }) {
+            Ok(file) => file,
+            Err(e) => {
+                warn!("{} ({}), using built-in selectors", e, path.display());
+                return selectors;
+            }
+        };
+        if file.version < selectors.version {
+            warn!(
+                "Ignoring {}: version {} is older than the built-in version {}",
+                path.display(),
+                file.version,
+                selectors.version
+            );
+            return selectors;
+        }
+
+        info!(
+            "Using {} selector override(s) from {}",
+            file.selectors.len(),
+            path.display()
+        );
+        selectors.version = file.version;
+        selectors.rules.extend(file.selectors);
+        selectors.overridden_by = Some(path);
+        selectors
+    }
+
+    /// Keys defined for this platform, sorted.
+    pub fn keys(&self) -> Vec<&str> {
+        let mut keys: Vec<&str> = self.rules.keys().map(|k| k.as_str()).collect();
+        keys.sort();
+        keys
+    }
+
+    pub fn chain(&self, key: &str) -> &[Rule] {
+        match self.rules.get(key) {
+            Some(chain) => &chain.0,
+            None => {
+                warn!("No {} selector named {}", self.platform, key);
+                &[]
+            }
+        }
+    }
+
+    /// The CSS rules of `key` as one selector list, for the script-based waits.
+    /// XPath fallbacks can't be part of it.
+    pub fn css(&self, key: &str) -> String {
+        self.chain(key)
+            .iter()
+            .filter_map(|rule| rule.css.as_deref())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// First element matched by the rules of `key`, in order.
+    pub(crate) async fn find<S: Scope>(&self, scope: &S, key: &str) -> Option<S::Element> {
+        self.find_rule(scope, key).await.map(|(element, _)| element)
+    }
+
+    async fn find_rule<S: Scope>(&self, scope: &S, key: &str) -> Option<(S::Element, &Rule)> {
+        for rule in self.chain(key) {
+            if let Some(locator) = rule.locator() {
+                if let Some(element) = scope.find(locator).await {
+                    return Some((element, rule));
+                }
+            }
+        }
+        None
+    }
+
+    /// Elements of the first rule of `key` that matches any.
+    pub(crate) async fn find_all<S: Scope>(&self, scope: &S, key: &str) -> Vec<S::Element> {
+        for rule in self.chain(key) {
+            if let Some(locator) = rule.locator() {
+                let elements = scope.find_all(locator).await;
+                if !elements.is_empty() {
+                    return elements;
+                }
+            }
+        }
+        Vec::new()
+    }
+
+    /// Number of elements matched by the first rule of `key` that matches any, and
+    /// that rule's position in the chain.
+    pub(crate) async fn count<S: Scope>(&self, scope: &S, key: &str) -> (usize, Option<usize>) {
+        for (index, rule) in self.chain(key).iter().enumerate() {
+            if let Some(locator) = rule.locator() {
+                let found = scope.find_all(locator).await.len();
+                if found > 0 {
+                    return (found, Some(index));
+                }
+            }
+        }
+        (0, None)
+    }
+
+    /// Text (or the rule's attribute) of the first match of `key`, `None` when
+    /// nothing matched.
+    pub(crate) async fn read<S: Scope>(&self, scope: &S, key: &str) -> Option<String> {
+        let (element, rule) = self.find_rule(scope, key).await?;
+        match &rule.attr {
+            Some(attr) => Some(element.attr(attr).await.unwrap_or_default()),
+            None => Some(element.text().await),
+        }
+    }
+
+    /// Like `read`, with an empty string when nothing matched.
+    pub(crate) async fn text<S: Scope>(&self, scope: &S, key: &str) -> String {
+        self.read(scope, key).await.unwrap_or_default()
+    }
+}
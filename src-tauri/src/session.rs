@@ -0,0 +1,120 @@
+// Browser sessions: one chromedriver process + one Chrome window each
+
+use log::info;
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::Child;
+use thirtyfour::prelude::*;
+use tokio::time::{sleep, Duration};
+
+use crate::chromedriver::ensure_chromedriver;
+
+/// A running chromedriver and the Chrome session it drives. Call `close` when done;
+/// dropping the session still kills chromedriver (and with it Chrome) so no process
+/// is left behind when a run fails or is cancelled.
+pub struct BrowserSession {
+    pub driver: WebDriver,
+    // None once handed over by `into_parts`
+    child: Option<Child>,
+}
+
+// Ask the OS for a free port instead of guessing, so parallel sessions don't collide
+fn free_port() -> Result<u16, String> {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .map_err(|e| format!("Failed to find a free port: {}", e))?;
+    listener
+        .local_addr()
+        .map(|addr| addr.port())
+        .map_err(|e| format!("Failed to find a free port: {}", e))
+}
+
+impl BrowserSession {
+    /// Starts chromedriver and Chrome. `profile_path` is a Chrome user data dir; Chrome
+    /// locks it, so only one session at a time may use the configured profile. With
+    /// `None` chromedriver creates a fresh temporary profile.
+    pub async fn start(profile_path: Option<String>) -> Result<Self, String> {
+        let driver_path = ensure_chromedriver().await.map_err(|e| e.to_string())?;
+
+        let driver_path_buf = PathBuf::from(driver_path);
+        let driver_dir = driver_path_buf.parent().ok_or("invalid driver path")?;
+
+        let chromedriver_executable = match std::env::consts::OS {
+            "linux" | "macos" => driver_dir.join("chromedriver_PATCHED"),
+            "windows" => driver_dir.join("chromedriver_PATCHED.exe"),
+            _ => return Err("Unsupported OS!".to_string()),
+        };
+
+        let port = free_port()?;
+
+        // Launch chromedriver
+        let mut child = std::process::Command::new(chromedriver_executable.as_os_str())
+            .arg(format!("--port={}", port))
+            .current_dir(driver_dir)
+            .spawn()
+            .map_err(|e| format!("failed to spawn chromedriver: {}", e))?;
+
+        // Wait a bit for chromedriver to start
+        sleep(Duration::from_secs(2)).await;
+
+        let mut caps = DesiredCapabilities::chrome();
+        if let Some(path) = profile_path.filter(|p| !p.is_empty()) {
+            caps.add_chrome_arg(&format!("--user-data-dir={}", path))
+                .unwrap();
+        }
+        caps.set_no_sandbox().unwrap();
+        caps.set_disable_dev_shm_usage().unwrap();
+        caps.add_chrome_arg("--disable-blink-features=AutomationControlled")
+            .unwrap();
+        caps.add_chrome_arg("window-size=1920,1080").unwrap();
+        caps.add_chrome_arg("user-agent=Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/102.0.0.0 Safari/537.36").unwrap();
+        caps.add_chrome_arg("disable-infobars").unwrap();
+        // Tabs of the pool load in the background; don't let Chrome throttle them
+        caps.add_chrome_arg("--disable-background-timer-throttling")
+            .unwrap();
+        caps.add_chrome_arg("--disable-renderer-backgrounding")
+            .unwrap();
+        caps.add_chrome_arg("--disable-backgrounding-occluded-windows")
+            .unwrap();
+        caps.add_chrome_option("excludeSwitches", ["enable-automation"])
+            .unwrap();
+
+        let driver = match WebDriver::new(&format!("http://localhost:{}", port), caps).await {
+            Ok(driver) => driver,
+            Err(e) => {
+                let _ = child.kill();
+                return Err(format!("failed to connect to chromedriver: {}", e));
+            }
+        };
+
+        info!("Browser session started on port {}", port);
+        Ok(BrowserSession {
+            driver,
+            child: Some(child),
+        })
+    }
+
+    pub async fn close(mut self) {
+        let _ = self.driver.clone().quit().await;
+        self.kill();
+    }
+
+    /// Hands the chromedriver process over to the caller, for sessions that should
+    /// outlive this struct (the "open Chrome" button).
+    pub fn into_parts(mut self) -> (WebDriver, Child) {
+        let child = self.child.take().expect("session already closed");
+        (self.driver.clone(), child)
+    }
+
+    fn kill(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+impl Drop for BrowserSession {
+    fn drop(&mut self) {
+        self.kill();
+    }
+}
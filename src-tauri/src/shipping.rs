@@ -0,0 +1,309 @@
+// Shipping (ongkir) estimation from the shop's city to a configured destination
+
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::models::ShippingEstimate;
+
+/// Stored in `<config dir>/satu-toko/shipping.json`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ShippingConfig {
+    pub enabled: bool,
+    pub destination_city: String,
+    // Kecamatan, matched before the city when the rate table has it
+    pub destination_district: String,
+    // Local CSV of courier rates; empty uses the built-in flat rates
+    pub rates_csv: String,
+    // Used when the product page doesn't show a weight
+    pub default_weight_grams: u32,
+    // Visit product detail pages to read the weight of basket items
+    pub fetch_weights: bool,
+}
+
+impl Default for ShippingConfig {
+    fn default() -> Self {
+        ShippingConfig {
+            enabled: false,
+            destination_city: String::new(),
+            destination_district: String::new(),
+            rates_csv: String::new(),
+            default_weight_grams: 1000,
+            fetch_weights: true,
+        }
+    }
+}
+
+fn config_file() -> Result<PathBuf, String> {
+    let config_dir = dirs::config_dir().ok_or("Could not determine config directory")?;
+    Ok(config_dir.join("satu-toko").join("shipping.json"))
+}
+
+pub fn load_config() -> ShippingConfig {
+    let path = match config_file() {
+        Ok(path) => path,
+        Err(_) => return ShippingConfig::default(),
+    };
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_config(config: &ShippingConfig) -> Result<(), String> {
+    let path = config_file()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("Failed to serialize shipping config: {}", e))?;
+    fs::write(path, content).map_err(|e| format!("Failed to write config: {}", e))
+}
+
+/// One courier service price between an origin and the destination.
+#[derive(Clone, Debug)]
+pub struct RateQuote {
+    pub courier: String,
+    pub price_per_kg: u64,
+    pub etd: Option<String>,
+}
+
+/// Backend that knows courier prices. Implementations must be cheap to query;
+/// they are called once per shop.
+pub trait RateTable: Send + Sync {
+    fn quotes(&self, origin: &str, destination: &ShippingConfig) -> Vec<RateQuote>;
+}
+
+/// Rough fallback when no rate CSV is configured: one price inside the
+/// destination city and one for everything else.
+pub struct FlatRateTable {
+    pub same_city_per_kg: u64,
+    pub other_per_kg: u64,
+}
+
+impl Default for FlatRateTable {
+    fn default() -> Self {
+        FlatRateTable {
+            same_city_per_kg: 10_000,
+            other_per_kg: 25_000,
+        }
+    }
+}
+
+impl RateTable for FlatRateTable {
+    fn quotes(&self, origin: &str, destination: &ShippingConfig) -> Vec<RateQuote> {
+        let same_city = !destination.destination_city.is_empty()
+            && places_match(origin, &destination.destination_city);
+        vec![RateQuote {
+            courier: "Estimasi".to_string(),
+            price_per_kg: if same_city {
+                self.same_city_per_kg
+            } else {
+                self.other_per_kg
+            },
+            etd: None,
+        }]
+    }
+}
+
+struct CsvRate {
+    courier: String,
+    origin: String,
+    destination: String,
+    price_per_kg: u64,
+    etd: Option<String>,
+}
+
+/// Offline rate table loaded from a CSV with the header
+/// `courier,origin,destination,price_per_kg,etd` (etd optional).
+pub struct CsvRateTable {
+    rates: Vec<CsvRate>,
+}
+
+impl CsvRateTable {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read rate table {}: {}", path, e))?;
+        Self::parse(&content)
+    }
+
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .trim(csv::Trim::All)
+            .from_reader(content.as_bytes());
+        let mut rates = Vec::new();
+        for record in reader.records() {
+            let record = record.map_err(|e| format!("Failed to read rate table: {}", e))?;
+            let line = record.position().map_or(0, |p| p.line());
+            if record.iter().all(str::is_empty) {
+                continue;
+            }
+            if record.len() < 4 {
+                return Err(format!("Rate table line {}: expected at least 4 columns", line));
+            }
+            let price_per_kg = record[3]
+                .replace('.', "")
+                .parse()
+                .map_err(|_| format!("Rate table line {}: invalid price '{}'", line, &record[3]))?;
+            rates.push(CsvRate {
+                courier: record[0].to_string(),
+                origin: record[1].to_string(),
+                destination: record[2].to_string(),
+                price_per_kg,
+                etd: record.get(4).filter(|v| !v.is_empty()).map(str::to_string),
+            });
+        }
+        Ok(CsvRateTable { rates })
+    }
+}
+
+impl RateTable for CsvRateTable {
+    fn quotes(&self, origin: &str, destination: &ShippingConfig) -> Vec<RateQuote> {
+        // Prefer rows for the kecamatan, fall back to rows for the city
+        let targets = [&destination.destination_district, &destination.destination_city];
+        for target in targets.iter().filter(|t| !t.is_empty()) {
+            let quotes: Vec<RateQuote> = self
+                .rates
+                .iter()
+                .filter(|r| places_match(origin, &r.origin) && places_match(target, &r.destination))
+                .map(|r| RateQuote {
+                    courier: r.courier.clone(),
+                    price_per_kg: r.price_per_kg,
+                    etd: r.etd.clone(),
+                })
+                .collect();
+            if !quotes.is_empty() {
+                return quotes;
+            }
+        }
+        Vec::new()
+    }
+}
+
+// Lowercase words of a place name without a leading "Kota" / "Kab." / "Kabupaten":
+// card locations sometimes carry it and rate tables sometimes don't
+fn place_words(place: &str) -> Vec<String> {
+    let lower = place.trim().to_lowercase();
+    let mut name = lower.as_str();
+    for prefix in ["kabupaten ", "kab. ", "kab.", "kota "] {
+        if let Some(rest) = name.strip_prefix(prefix) {
+            name = rest;
+            break;
+        }
+    }
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// "Jakarta Barat" matches "jakarta barat" and "Kota Jakarta Barat"; "Jakarta" alone
+// matches any Jakarta. Only whole words count, so "Kotabaru" doesn't match "Pekanbaru".
+fn places_match(place: &str, pattern: &str) -> bool {
+    let place = place_words(place);
+    let pattern = place_words(pattern);
+    let (shorter, longer) = if place.len() <= pattern.len() {
+        (&place, &pattern)
+    } else {
+        (&pattern, &place)
+    };
+    !shorter.is_empty() && longer.windows(shorter.len()).any(|words| words == shorter.as_slice())
+}
+
+pub struct ShippingEstimator {
+    config: ShippingConfig,
+    table: Box<dyn RateTable>,
+}
+
+impl ShippingEstimator {
+    /// Returns `None` when shipping estimation is turned off.
+    pub fn from_config(config: ShippingConfig) -> Result<Option<Self>, String> {
+        if !config.enabled || config.destination_city.trim().is_empty() {
+            return Ok(None);
+        }
+        let table: Box<dyn RateTable> = if config.rates_csv.trim().is_empty() {
+            Box::new(FlatRateTable::default())
+        } else {
+            Box::new(CsvRateTable::load(config.rates_csv.trim())?)
+        };
+        Ok(Some(ShippingEstimator { config, table }))
+    }
+
+    pub fn config(&self) -> &ShippingConfig {
+        &self.config
+    }
+
+    /// Cheapest courier for the given origin city and total weight. Couriers charge
+    /// per started kilogram with a 1 kg minimum.
+    pub fn estimate(&self, origin: &str, weight_grams: u64) -> Option<ShippingEstimate> {
+        let billable_kg = weight_grams.max(1).div_ceil(1000);
+        let best = self
+            .table
+            .quotes(origin, &self.config)
+            .into_iter()
+            .min_by_key(|q| q.price_per_kg)?;
+
+        info!(
+            "Ongkir {} -> {} ({} g) via {}: {}",
+            origin, self.config.destination_city, weight_grams, best.courier, best.price_per_kg.saturating_mul(billable_kg)
+        );
+
+        Some(ShippingEstimate {
+            courier: best.courier,
+            origin: origin.to_string(),
+            destination: if self.config.destination_district.is_empty() {
+                self.config.destination_city.clone()
+            } else {
+                format!(
+                    "{}, {}",
+                    self.config.destination_district, self.config.destination_city
+                )
+            },
+            weight_grams,
+            cost: best.price_per_kg.saturating_mul(billable_kg),
+            etd: best.etd,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_table_fields_can_be_quoted() {
+        let table = CsvRateTable::parse(
+            "courier,origin,destination,price_per_kg,etd\n\
+             JNE REG,\"Jakarta, Barat\",Bandung,\"12.000\",2-3 hari\n\
+             \n\
+             SiCepat,Surabaya,Malang,9000\n",
+        )
+        .unwrap();
+        assert_eq!(table.rates.len(), 2);
+        assert_eq!(table.rates[0].origin, "Jakarta, Barat");
+        assert_eq!(table.rates[0].price_per_kg, 12000);
+        assert_eq!(table.rates[0].etd.as_deref(), Some("2-3 hari"));
+        assert_eq!(table.rates[1].etd, None);
+
+        let err = CsvRateTable::parse("courier,origin,destination,price_per_kg\nJNE,Jakarta,Bandung,murah\n")
+            .err()
+            .unwrap();
+        assert_eq!(err, "Rate table line 2: invalid price 'murah'");
+    }
+
+    #[test]
+    fn places_match_on_whole_words() {
+        assert!(places_match("Kota Jakarta Barat", "Jakarta Barat"));
+        assert!(places_match("Jakarta Barat", "jakarta"));
+        assert!(places_match("Kab. Bogor", "Kabupaten Bogor"));
+        assert!(places_match("Kotabaru", "Kotabaru"));
+        assert!(!places_match("Kotabaru", "Pekanbaru"));
+        assert!(!places_match("Kotamobagu", "Mobagu"));
+        assert!(!places_match("Jakarta Barat", "Jakarta Timur"));
+        assert!(!places_match("", "Jakarta"));
+    }
+}
@@ -0,0 +1,76 @@
+// Shopee shop names by shop id. Product links only carry the shop id, so the names
+// read from shop pages are kept in `<config dir>/satu-toko/shopee_shops.json`: the
+// next run that finds a shop shows its name from the start, and still has one when
+// the shop page doesn't.
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+fn cache_file() -> Option<PathBuf> {
+    let config_dir = dirs::config_dir()?;
+    Some(config_dir.join("satu-toko").join("shopee_shops.json"))
+}
+
+/// Known shop names. `default()`, like the names of a recorded session, is an
+/// in-memory cache that is never written.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ShopNames {
+    names: BTreeMap<String, String>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+    #[serde(skip)]
+    changed: bool,
+}
+
+impl ShopNames {
+    /// The names learnt by earlier runs.
+    pub fn load() -> Self {
+        let Some(path) = cache_file() else {
+            return ShopNames::default();
+        };
+        let mut names: ShopNames = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Ignoring unreadable shop name cache {}: {}", path.display(), e);
+                ShopNames::default()
+            }),
+            Err(_) => ShopNames::default(),
+        };
+        names.path = Some(path);
+        names
+    }
+
+    pub fn get(&self, shop_id: &str) -> Option<&str> {
+        self.names.get(shop_id).map(String::as_str)
+    }
+
+    pub fn insert(&mut self, shop_id: &str, name: &str) {
+        let name = name.trim();
+        if name.is_empty() || self.get(shop_id) == Some(name) {
+            return;
+        }
+        self.names.insert(shop_id.to_string(), name.to_string());
+        self.changed = true;
+    }
+
+    /// Writes the cache back if a run learnt a new name.
+    pub fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if !self.changed {
+            return Ok(());
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create config directory: {}", e))?;
+        }
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize shop names: {}", e))?;
+        fs::write(path, content).map_err(|e| format!("Failed to write shop names: {}", e))?;
+        info!("Saved {} Shopee shop name(s)", self.names.len());
+        Ok(())
+    }
+}
@@ -0,0 +1,188 @@
+// Tab pool: several shops searched at once in one browser session
+//
+// WebDriver commands always go to the session's current tab, so a tab has to be
+// locked (and switched to) for every command. Page loads don't need the lock:
+// `Tab::open` starts the navigation and returns, and the `wait` helpers run on a
+// tab lock only for each check, so other tabs keep working while a page loads.
+//
+// Since `open` returns before the page is replaced, the page being left is marked
+// first and the wait helpers ignore marked pages; otherwise a selector present on
+// both pages (the shop header) would match the old one.
+
+use log::info;
+use serde_json::Value;
+use std::ops::Deref;
+use std::sync::Mutex as StdMutex;
+use thirtyfour::prelude::*;
+use thirtyfour::WindowHandle;
+use tokio::sync::{Mutex, MutexGuard, Semaphore, SemaphorePermit};
+use tokio::time::{sleep, Duration, Instant};
+
+use crate::wait::Page;
+
+pub struct TabPool<'a> {
+    driver: &'a WebDriver,
+    // Held while a tab is the current one
+    session: Mutex<()>,
+    free: StdMutex<Vec<WindowHandle>>,
+    // Handles opened by the pool, closed again by `close`
+    opened: Vec<WindowHandle>,
+    original: WindowHandle,
+    permits: Semaphore,
+    // Minimum time between two navigations, across all tabs
+    politeness: Duration,
+    last_navigation: Mutex<Option<Instant>>,
+}
+
+impl<'a> TabPool<'a> {
+    /// Opens `size - 1` extra tabs next to the current one.
+    pub async fn open(driver: &'a WebDriver, size: usize, politeness: Duration) -> Result<Self, String> {
+        let size = size.max(1);
+        let original = driver
+            .window()
+            .await
+            .map_err(|e| format!("Failed to read current tab: {}", e))?;
+
+        let mut opened = Vec::new();
+        for _ in 1..size {
+            match driver.new_tab().await {
+                Ok(handle) => opened.push(handle),
+                // Fewer tabs only means less parallelism
+                Err(e) => info!("Failed to open extra tab: {}", e),
+            }
+        }
+
+        let mut free = vec![original.clone()];
+        free.extend(opened.iter().cloned());
+        let tabs = free.len();
+        info!("Tab pool with {} tab(s), {:?} between navigations", tabs, politeness);
+
+        Ok(TabPool {
+            driver,
+            session: Mutex::new(()),
+            free: StdMutex::new(free),
+            opened,
+            original,
+            permits: Semaphore::new(tabs),
+            politeness,
+            last_navigation: Mutex::new(None),
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.opened.len() + 1
+    }
+
+    /// Waits for a free tab.
+    pub async fn acquire(&self) -> Tab<'_, 'a> {
+        let permit = self.permits.acquire().await.expect("tab pool semaphore closed");
+        let handle = self
+            .free
+            .lock()
+            .unwrap()
+            .pop()
+            .expect("a permit always comes with a free tab");
+        Tab {
+            pool: self,
+            handle: Some(handle),
+            _permit: permit,
+        }
+    }
+
+    /// Closes the extra tabs and goes back to the original one.
+    pub async fn close(self) {
+        let _guard = self.session.lock().await;
+        for handle in &self.opened {
+            if self.driver.switch_to_window(handle.clone()).await.is_ok() {
+                let _ = self.driver.close_window().await;
+            }
+        }
+        let _ = self.driver.switch_to_window(self.original.clone()).await;
+    }
+}
+
+pub struct Tab<'p, 'a> {
+    pool: &'p TabPool<'a>,
+    handle: Option<WindowHandle>,
+    _permit: SemaphorePermit<'p>,
+}
+
+/// Exclusive access to the session with this tab as the current one.
+pub struct TabGuard<'p> {
+    driver: &'p WebDriver,
+    _session: MutexGuard<'p, ()>,
+}
+
+impl Deref for TabGuard<'_> {
+    type Target = WebDriver;
+
+    fn deref(&self) -> &WebDriver {
+        self.driver
+    }
+}
+
+impl Tab<'_, '_> {
+    fn handle(&self) -> WindowHandle {
+        self.handle.clone().expect("tab handle is only taken on drop")
+    }
+
+    /// Locks the session and switches to this tab. Keep the guard only as long as
+    /// needed; other tabs wait meanwhile.
+    pub async fn lock(&self) -> Result<TabGuard<'_>, String> {
+        let session = self.pool.session.lock().await;
+        self.pool
+            .driver
+            .switch_to_window(self.handle())
+            .await
+            .map_err(|e| format!("Failed to switch tab: {}", e))?;
+        Ok(TabGuard {
+            driver: self.pool.driver,
+            _session: session,
+        })
+    }
+
+    /// Waits until the pool's politeness delay since the previous navigation (of any
+    /// tab) has passed, and claims the next slot.
+    pub async fn wait_turn(&self) {
+        let mut last = self.pool.last_navigation.lock().await;
+        if let Some(previous) = *last {
+            let ready = previous + self.pool.politeness;
+            let now = Instant::now();
+            if ready > now {
+                sleep(ready - now).await;
+            }
+        }
+        *last = Some(Instant::now());
+    }
+
+    /// Starts loading `url` in this tab without waiting for it to load.
+    pub async fn open(&self, url: &str) -> Result<(), String> {
+        self.wait_turn().await;
+        let driver = self.lock().await?;
+        driver.expect_navigation().await;
+        driver
+            .execute("window.location.href = arguments[0];", vec![url.into()])
+            .await
+            .map_err(|e| format!("Failed to open {}: {}", url, e))?;
+        Ok(())
+    }
+}
+
+impl Page for Tab<'_, '_> {
+    async fn run(&self, script: &str, args: Vec<Value>) -> Option<Value> {
+        let driver = self.lock().await.ok()?;
+        driver.run(script, args).await
+    }
+
+    async fn navigate(&self, url: &str) -> Result<(), String> {
+        self.open(url).await
+    }
+}
+
+impl Drop for Tab<'_, '_> {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            self.pool.free.lock().unwrap().push(handle);
+        }
+    }
+}
@@ -0,0 +1,198 @@
+// Explicit wait conditions used instead of fixed sleeps: return as soon as the page
+// is ready on a fast connection, keep waiting (up to the timeout) on a slow one.
+
+use log::info;
+use serde_json::{json, Value};
+use std::future::Future;
+use std::sync::Arc;
+use thirtyfour::prelude::*;
+use tokio::time::{sleep, Duration, Instant};
+
+use crate::archive::SessionArchive;
+use crate::models::ScrapeOptions;
+use crate::navigate::PAGE_PROBLEM;
+
+// Delay between two checks of a condition
+const POLL: Duration = Duration::from_millis(250);
+
+// Marks the page as being left, until the next page replaces its window
+pub(crate) const LEAVING: &str = "window.__satuTokoLeaving = true;";
+
+// Index of the first selector found on the page, -1 for none. Pages marked as being
+// left by `Page::expect_navigation` never match, so a wait doesn't succeed on the previous page.
+const FIND_ANY: &str = r#"
+if (window.__satuTokoLeaving) return -1;
+const selectors = arguments[0];
+for (let i = 0; i < selectors.length; i++) {
+    if (document.querySelector(selectors[i])) return i;
+}
+return -1;
+"#;
+
+// Load state and number of requests made so far, -1 for a page being left
+const NETWORK: &str = r#"
+if (window.__satuTokoLeaving || document.readyState !== "complete") return -1;
+return performance.getEntriesByType("resource").length;
+"#;
+
+const COUNT: &str = r#"
+if (window.__satuTokoLeaving) return -1;
+return document.querySelectorAll(arguments[0]).length;
+"#;
+
+/// Timeouts of the wait helpers, taken from `ScrapeOptions`.
+#[derive(Clone, Debug)]
+pub struct Waits {
+    // Upper bound for any single wait
+    pub timeout: Duration,
+    // How long nothing may change before a page counts as settled
+    pub settle: Duration,
+    // Extra attempts `navigate::open_page` makes for a page that doesn't load
+    pub retries: u32,
+    // Session being recorded or replayed by `navigate::open_page`
+    pub archive: Option<Arc<SessionArchive>>,
+}
+
+impl Waits {
+    pub fn from_options(options: &ScrapeOptions) -> Self {
+        Waits {
+            timeout: Duration::from_secs(options.wait_timeout_secs.max(1)),
+            settle: Duration::from_millis(options.settle_ms),
+            retries: options.max_retries,
+            archive: options.archive.clone(),
+        }
+    }
+}
+
+impl Default for Waits {
+    fn default() -> Self {
+        Waits::from_options(&ScrapeOptions::default())
+    }
+}
+
+/// Something scripts can run in: the session's current tab, or one tab of a pool.
+/// The checks the waits poll are scripts by default; the fake browser of the tests
+/// answers them from its DOM instead.
+pub(crate) trait Page {
+    // None when the script failed, e.g. while the page is being replaced
+    async fn run(&self, script: &str, args: Vec<Value>) -> Option<Value>;
+
+    // Starts loading `url`; may return before the page has loaded
+    async fn navigate(&self, url: &str) -> Result<(), String>;
+
+    /// Marks the current page as being left, for navigations started by the page
+    /// itself (submitting a search form), so the waits don't succeed on it while the
+    /// next page loads.
+    async fn expect_navigation(&self) {
+        let _ = self.run(LEAVING, vec![]).await;
+    }
+
+    /// Index of the first of `selectors` on the page right now.
+    async fn find_any(&self, selectors: &[&str]) -> Option<usize> {
+        number(self, FIND_ANY, vec![json!(selectors)])
+            .await
+            .map(|i| i as usize)
+    }
+
+    /// Number of `selector` matches, `None` for a page being left.
+    async fn count(&self, selector: &str) -> Option<i64> {
+        number(self, COUNT, vec![json!(selector)]).await
+    }
+
+    /// Requests the page has made so far, `None` while it is still loading.
+    async fn requests(&self) -> Option<i64> {
+        number(self, NETWORK, vec![]).await
+    }
+
+    /// What is wrong with the page currently shown, if anything obvious: a browser or
+    /// server error page, or (with `check_blank`) a page without any text.
+    async fn problem(&self, check_blank: bool) -> Option<String> {
+        match self.run(PAGE_PROBLEM, vec![json!(check_blank)]).await {
+            Some(value) => value.as_str().map(|s| s.to_string()),
+            None => Some("page did not respond".to_string()),
+        }
+    }
+}
+
+impl Page for WebDriver {
+    async fn run(&self, script: &str, args: Vec<Value>) -> Option<Value> {
+        self.execute(script, args).await.ok().map(|ret| ret.json().clone())
+    }
+
+    async fn navigate(&self, url: &str) -> Result<(), String> {
+        self.goto(url)
+            .await
+            .map_err(|e| format!("Failed to open {}: {}", url, e))
+    }
+}
+
+async fn number<P: Page + ?Sized>(page: &P, script: &str, args: Vec<Value>) -> Option<i64> {
+    page.run(script, args)
+        .await
+        .and_then(|v| v.as_i64())
+        .filter(|n| *n >= 0)
+}
+
+/// Waits until one of `selectors` is on the page. Returns the index of the selector
+/// that matched, or `None` on timeout.
+pub(crate) async fn any_selector<P: Page>(
+    page: &P,
+    selectors: &[&str],
+    timeout: Duration,
+) -> Option<usize> {
+    let start = Instant::now();
+    loop {
+        if let Some(index) = page.find_any(selectors).await {
+            return Some(index);
+        }
+        if start.elapsed() >= timeout {
+            info!("Timed out waiting for {}", selectors.join(" | "));
+            return None;
+        }
+        sleep(POLL).await;
+    }
+}
+
+// Polls `probe` until it returns the same value for `waits.settle`. Returns the last
+// value seen, and whether it settled before the timeout.
+async fn settled<F, Fut>(probe: F, waits: &Waits) -> (Option<i64>, bool)
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Option<i64>>,
+{
+    let start = Instant::now();
+    let mut last: Option<i64> = None;
+    let mut since = Instant::now();
+    loop {
+        let value = probe().await;
+        if value != last {
+            last = value;
+            since = Instant::now();
+        } else if value.is_some() && since.elapsed() >= waits.settle {
+            return (last, true);
+        }
+        if start.elapsed() >= waits.timeout {
+            return (last, false);
+        }
+        sleep(POLL).await;
+    }
+}
+
+/// Waits until the page has loaded and made no new requests for `waits.settle`.
+pub(crate) async fn network_idle<P: Page>(page: &P, waits: &Waits) -> bool {
+    let (_, idle) = settled(|| page.requests(), waits).await;
+    if !idle {
+        info!("Timed out waiting for network idle");
+    }
+    idle
+}
+
+/// Waits until the number of `selector` matches stops changing for `waits.settle`
+/// (lazy-loaded cards after a scroll or page change) and returns that number.
+pub(crate) async fn stable_count<P: Page>(page: &P, selector: &str, waits: &Waits) -> usize {
+    let (count, stable) = settled(|| page.count(selector), waits).await;
+    if !stable {
+        info!("Timed out waiting for {} to stop changing", selector);
+    }
+    count.unwrap_or(0) as usize
+}
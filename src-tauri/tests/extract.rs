@@ -0,0 +1,321 @@
+// Extraction against saved marketplace pages in tests/fixtures, so a selector or
+// parser change can be checked without a browser. When a platform changes its
+// markup, save the new page over the fixture and update the expectations.
+
+use kuchikiki::NodeRef;
+use std::fs;
+use std::path::PathBuf;
+
+use satu_toko_lib::extract::{self, ResultCount};
+use satu_toko_lib::models::{Badge, Condition, Product, QueryFilter};
+use satu_toko_lib::parse::{parse_product_detail, parse_result_total, parse_shop_profile, parse_stock};
+use satu_toko_lib::selectors::Selectors;
+
+fn fixture_html(path: &str) -> String {
+    let file = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(path);
+    fs::read_to_string(&file).unwrap_or_else(|e| panic!("{}: {}", file.display(), e))
+}
+
+fn fixture(path: &str) -> NodeRef {
+    extract::parse_html(&fixture_html(path))
+}
+
+fn alts(scope: &NodeRef, sel: &Selectors) -> Vec<String> {
+    extract::select_all(scope, sel, "badge_images")
+        .iter()
+        .filter_map(|img| extract::attr(img, "alt"))
+        .collect()
+}
+
+fn tokopedia_cards(page: &str, key: &str) -> Vec<Product> {
+    let sel = Selectors::builtin("tokopedia");
+    let document = fixture(page);
+    extract::select_all(&document, &sel, key)
+        .iter()
+        .filter_map(|card| extract::tokopedia_card(card, &sel))
+        .collect()
+}
+
+#[test]
+fn tokopedia_search_cards() {
+    let sel = Selectors::builtin("tokopedia");
+    let document = fixture("tokopedia/search.html");
+    // The "see all" link is matched too but isn't a product
+    assert_eq!(extract::select_all(&document, &sel, "seed_card").len(), 4);
+
+    let cards = tokopedia_cards("tokopedia/search.html", "seed_card");
+    assert_eq!(cards.len(), 3);
+
+    let first = &cards[0];
+    assert_eq!(first.name, "Kabel NYM 2x1.5 Eterna 50m");
+    assert_eq!(first.price, "Rp450.000");
+    assert_eq!(first.shop, "Toko Listrik Jaya");
+    assert_eq!(first.location, "Jakarta Barat");
+    assert!(first.photo.ends_with("kabel-nym-eterna.jpg"), "{}", first.photo);
+    assert!(first.link.starts_with("https://www.tokopedia.com/tokolistrikjaya/"));
+    assert_eq!(first.rating, Some(4.8));
+    assert_eq!(first.sold_count, Some(250));
+    assert_eq!(first.badges, vec![Badge::PowerMerchant]);
+
+    // Empty first span, discount and struck-through price
+    let second = &cards[1];
+    assert_eq!(second.name, "Kabel NYM Supreme 3x2.5 50m");
+    assert_eq!(second.price, "Rp1.150.000");
+    assert_eq!(second.location, "Kota Tangerang");
+    assert_eq!(second.original_price.as_deref(), Some("Rp1.300.000"));
+    assert_eq!(second.discount_percent, Some(12));
+    assert_eq!(second.rating, Some(5.0));
+    assert_eq!(second.sold_count, Some(1000));
+    assert!(second.badges.contains(&Badge::OfficialStore));
+
+    // Protocol-relative link, no stats
+    let third = &cards[2];
+    assert!(third.link.starts_with("https://www.tokopedia.com/listrikmurah/"), "{}", third.link);
+    assert_eq!(third.shop, "Listrik Murah");
+    assert_eq!(third.price, "Rp9.500");
+    assert_eq!(third.rating, None);
+    assert_eq!(third.sold_count, None);
+}
+
+#[test]
+fn tokopedia_shop_page() {
+    let sel = Selectors::builtin("tokopedia");
+    let cards = tokopedia_cards("tokopedia/shop.html", "shop_card");
+    assert_eq!(cards.len(), 2);
+    assert_eq!(cards[0].name, "Fitting Lampu E27 Broco");
+    assert_eq!(cards[0].price, "Rp12.500");
+    assert_eq!(cards[0].location, "Jakarta Barat");
+    assert_eq!(
+        cards[0].link,
+        "https://www.tokopedia.com/tokolistrikjaya/fitting-lampu-e27-broco-1730101"
+    );
+    assert_eq!(cards[0].rating, Some(4.9));
+    assert_eq!(cards[0].sold_count, Some(100));
+    assert_eq!(cards[1].name, "Fitting Lampu Plafon E27");
+
+    // The live scraper reads the header's ancestor through XPath; its parent holds
+    // the same lines here
+    let document = fixture("tokopedia/shop.html");
+    let header = extract::select_all(&document, &sel, "shop_header");
+    assert_eq!(header.len(), 1);
+    assert_eq!(extract::text(&header[0]), "Toko Listrik Jaya");
+    let block = header[0].parent().expect("header has a parent");
+    let profile = parse_shop_profile(&extract::text_lines(&block), &alts(&block, &sel));
+    assert_eq!(profile.rating, Some(4.9));
+    assert_eq!(profile.review_count, Some(1200));
+    assert_eq!(profile.response_time.as_deref(), Some("± 1 jam pesanan diproses"));
+    assert_eq!(profile.joined.as_deref(), Some("2019"));
+    assert_eq!(profile.badges, vec![Badge::PowerMerchant]);
+}
+
+#[test]
+fn tokopedia_product_page() {
+    let sel = Selectors::builtin("tokopedia");
+    let document = fixture("tokopedia/product.html");
+    assert_eq!(extract::select_all(&document, &sel, "product_ready").len(), 1);
+
+    // The script and the description also mention a stock; only the label counts
+    let detail = parse_product_detail(&extract::text_lines(&document));
+    assert_eq!(detail.stock, None);
+    let stock = extract::read(&document, &sel, "product_stock").unwrap_or_default();
+    assert_eq!(parse_stock(&stock), Some(25));
+    assert_eq!(detail.condition, Some(Condition::New));
+    assert_eq!(detail.min_order, Some(1));
+    assert_eq!(detail.weight_grams, Some(4500));
+
+    let variants: Vec<String> = extract::select_all(&document, &sel, "product_variant")
+        .iter()
+        .map(extract::text)
+        .collect();
+    assert_eq!(variants.len(), 2);
+    assert_eq!(
+        extract::read(&document, &sel, "product_price").as_deref(),
+        Some("Rp450.000")
+    );
+    let description = extract::read(&document, &sel, "product_description").unwrap_or_default();
+    assert!(description.starts_with("Kabel NYM"), "{}", description);
+}
+
+#[test]
+fn tokopedia_pages_over_http() {
+    // What HTTP mode gets: whole pages, read without a browser
+    let sel = Selectors::builtin("tokopedia");
+    let search = fixture_html("tokopedia/search.html");
+    assert_eq!(extract::find_any(&search, &[&sel.css("seed_card"), &sel.css("empty_state")]), Some(0));
+    let cards = extract::page_cards(&search, &sel, "seed_card");
+    assert_eq!(cards.len(), 3);
+    assert_eq!(cards[0].shop, "Toko Listrik Jaya");
+
+    let shop = fixture_html("tokopedia/shop.html");
+    assert_eq!(extract::page_cards(&shop, &sel, "shop_card").len(), 2);
+    let profile = extract::tokopedia_shop_profile(&shop, &sel);
+    assert_eq!(profile.rating, Some(4.9));
+    assert_eq!(profile.joined.as_deref(), Some("2019"));
+    assert_eq!(profile.badges, vec![Badge::PowerMerchant]);
+
+    // A bot check page has neither results nor the empty state
+    assert_eq!(extract::find_any("<html><body>Verifikasi</body></html>", &[&sel.css("seed_card")]), None);
+}
+
+#[test]
+fn search_result_counts() {
+    assert_eq!(
+        parse_result_total("Menampilkan 1 - 60 barang dari total 12,3rb untuk \"kabel\""),
+        Some(12300)
+    );
+    assert_eq!(parse_result_total("Dari total\n845\nuntuk \"fitting\""), Some(845));
+    assert_eq!(parse_result_total("Hasil pencarian"), None);
+
+    // Without a stated total, the cards on the page that pass the filter; there may
+    // be more on later pages
+    let sel = Selectors::builtin("tokopedia");
+    let search = fixture_html("tokopedia/search.html");
+    let count = extract::result_count(&search, &sel, &QueryFilter::default());
+    assert_eq!(count, ResultCount { total: 3, at_least: true });
+    let cheap = QueryFilter {
+        max_price: Some(10_000),
+        ..Default::default()
+    };
+    assert_eq!(extract::result_count(&search, &sel, &cheap).total, 1);
+
+    // Shopee states its page count: two pages of two priced cards
+    let sel = Selectors::builtin("shopee");
+    let search = fixture_html("shopee/search.html");
+    let count = extract::result_count(&search, &sel, &QueryFilter::default());
+    assert_eq!(count, ResultCount { total: 4, at_least: false });
+}
+
+#[test]
+fn shopee_search_cards() {
+    let sel = Selectors::builtin("shopee");
+    let document = fixture("shopee/search.html");
+
+    // Seed cards are the links themselves, including "find similar" links
+    let links = extract::select_all(&document, &sel, "seed_card");
+    assert_eq!(links.len(), 4);
+    let cards: Vec<Product> = links
+        .iter()
+        .filter_map(|card| extract::shopee_card(card, &sel))
+        .collect();
+    // Minus the "find similar" link and the card without a price
+    assert_eq!(cards.len(), 2);
+
+    let first = &cards[0];
+    assert_eq!(first.name, "Kabel NYM 2x1.5 Eterna 50m");
+    assert_eq!(first.price, "Rp445.000");
+    assert_eq!(first.shop, "124455053");
+    assert_eq!(first.location, "KOTA JAKARTA BARAT");
+    assert!(first.photo.ends_with("id-kabel-eterna.webp"), "{}", first.photo);
+    assert!(first.link.starts_with("https://shopee.co.id/Kabel-NYM-2x1.5-Eterna-50m-i."));
+    assert_eq!(first.discount_percent, Some(11));
+    assert_eq!(first.rating, Some(4.9));
+    assert_eq!(first.sold_count, Some(1000));
+
+    // Price without the accessibility label: second rule of the chain
+    let second = &cards[1];
+    assert_eq!(second.price, "Rp1.125.000");
+    assert_eq!(second.shop, "88776655");
+    assert_eq!(second.sold_count, Some(250));
+    assert_eq!(second.badges, vec![Badge::Mall]);
+
+    // In a shop search the card is the result item, holding the link
+    let items: Vec<Product> = extract::select_all(&document, &sel, "search_item")
+        .iter()
+        .filter_map(|item| extract::shopee_card(item, &sel))
+        .collect();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].link, first.link);
+}
+
+#[test]
+fn shopee_shop_and_product_pages() {
+    let sel = Selectors::builtin("shopee");
+
+    let document = fixture("shopee/shop.html");
+    let header = extract::select_all(&document, &sel, "shop_profile");
+    assert_eq!(header.len(), 1);
+    let profile = parse_shop_profile(&extract::text_lines(&header[0]), &alts(&header[0], &sel));
+    assert_eq!(profile.rating, Some(4.9));
+    assert_eq!(profile.review_count, Some(12300));
+    assert_eq!(profile.response_time.as_deref(), Some("98% (Hitungan Jam)"));
+    assert_eq!(profile.joined.as_deref(), Some("5 tahun lalu"));
+    assert_eq!(profile.online_status.as_deref(), Some("Aktif 5 menit lalu"));
+    assert_eq!(profile.badges, vec![Badge::Mall]);
+
+    let document = fixture("shopee/product.html");
+    let shop = extract::select_all(&document, &sel, "product_shop");
+    assert_eq!(shop.len(), 1);
+    let link = extract::select_all(&shop[0], &sel, "product_shop_link");
+    assert_eq!(
+        link.first().and_then(|a| extract::attr(a, "href")).as_deref(),
+        Some("/tokolistrikjaya_official")
+    );
+
+    let detail = parse_product_detail(&extract::text_lines(&document));
+    let stock = extract::read(&document, &sel, "product_stock").unwrap_or_default();
+    assert_eq!(parse_stock(&stock), Some(120));
+    assert_eq!(detail.condition, Some(Condition::New));
+    assert_eq!(detail.weight_grams, Some(450));
+    assert_eq!(extract::select_all(&document, &sel, "product_variant").len(), 2);
+}
+
+#[test]
+fn card_from_outer_html() {
+    // What the live scrapers pass in: one card's outerHTML
+    let sel = Selectors::builtin("tokopedia");
+    let html = r#"<a href="/tokolistrikjaya/stop-kontak-1730200"><div>
+        <div><img alt="product-image" src="https://images.tokopedia.net/stop-kontak.jpg"></div>
+        <div>
+          <div><span>Stop Kontak Broco 4 Lubang</span></div>
+          <div>Rp35.000</div>
+          <div><span></span><span>Jakarta Barat</span></div>
+        </div>
+    </div></a>"#;
+    let product = extract::card_from_html(html, &sel).expect("card is a product");
+    assert_eq!(product.name, "Stop Kontak Broco 4 Lubang");
+    assert_eq!(product.price, "Rp35.000");
+    assert_eq!(product.location, "Jakarta Barat");
+    assert_eq!(product.link, "https://www.tokopedia.com/tokolistrikjaya/stop-kontak-1730200");
+
+    let sel = Selectors::builtin("shopee");
+    let html = r#"<a href="/find_similar_products?catid=1&amp;itemid=2">Temukan Produk Serupa</a>"#;
+    assert!(extract::card_from_html(html, &sel).is_none());
+}
+
+#[test]
+fn shopee_ids_from_links() {
+    let ids = extract::shopee_ids("https://shopee.co.id/Celana-Pendek-i.124455053.29705222804")
+        .expect("name-i.shop.item link");
+    assert_eq!(ids.shop_id, "124455053");
+    assert_eq!(ids.item_id, "29705222804");
+
+    // Query, fragment and a relative link
+    assert_eq!(
+        extract::shopee_shop_id("/Kabel-i.88776655.1122334455?sp_atk=x.y#reviews").as_deref(),
+        Some("88776655")
+    );
+    // A product name that itself contains "-i."
+    assert_eq!(
+        extract::shopee_shop_id("https://shopee.co.id/Kabel-i.Lite-i.88776655.1122334455").as_deref(),
+        Some("88776655")
+    );
+    let ids = extract::shopee_ids("https://shopee.co.id/product/124455053/29705222804/")
+        .expect("product/shop/item link");
+    assert_eq!((ids.shop_id.as_str(), ids.item_id.as_str()), ("124455053", "29705222804"));
+
+    // Shop pages and other links carry no product
+    assert_eq!(extract::shopee_shop_id("https://shopee.co.id/tokolistrikjaya"), None);
+    assert_eq!(extract::shopee_shop_id("https://shopee.co.id/shop/124455053"), None);
+    assert_eq!(extract::shopee_shop_id("https://shopee.co.id/Promo-i.abc.123"), None);
+
+    assert_eq!(extract::shopee_shop_url("124455053"), "https://shopee.co.id/shop/124455053");
+    assert_eq!(
+        extract::shopee_shop_id_from_url("https://shopee.co.id/shop/124455053").as_deref(),
+        Some("124455053")
+    );
+    // Results saved before shops were grouped by id
+    assert_eq!(extract::shopee_shop_id_from_url("https://shopee.co.id/tokolistrikjaya"), None);
+}
@@ -3,11 +3,122 @@ import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import "./index.css";
 
+const EMPTY_FILTER = {
+  min_price: null,
+  max_price: null,
+  include_words: [],
+  exclude_words: [],
+  min_rating: null,
+  condition: null,
+};
+
+const BADGE_LABELS = {
+  official_store: "Official Store",
+  power_merchant: "Power Merchant",
+  star_seller: "Star Seller",
+  mall: "Mall",
+};
+
+const formatRupiah = (amount) =>
+  "Rp" + Number(amount || 0).toLocaleString("id-ID");
+
+// "10x kabel NYM 2x1.5" -> quantity 10; sizes like "2x1.5" need the space after "x"
+// 83 -> "01:23"
+function formatDuration(secs) {
+  const m = Math.floor(secs / 60);
+  const s = secs % 60;
+  return `${String(m).padStart(2, "0")}:${String(s).padStart(2, "0")}`;
+}
+
+// Age of a cached result: 45 -> "baru saja", 600 -> "10 menit lalu", 7200 -> "2 jam lalu"
+function formatAge(secs) {
+  if (secs < 60) return "baru saja";
+  if (secs < 3600) return `${Math.floor(secs / 60)} menit lalu`;
+  return `${Math.floor(secs / 3600)} jam lalu`;
+}
+
+// One line for a backend "status" progress event
+function describeStatus(status) {
+  const platform = status.platform === "shopee" ? "Shopee" : "Tokopedia";
+  const parts = [];
+  if (status.phase === "collecting_shops") {
+    parts.push(
+      `${platform}: mengumpulkan toko (${status.products_found}/${status.target_products} produk)`,
+    );
+  } else {
+    parts.push(
+      `${platform}: toko ${status.shop} dari ${status.shops}, query ${status.query} dari ${status.queries}`,
+    );
+    parts.push(`${status.shops_done} toko selesai`);
+    parts.push(`${status.products_found} produk`);
+  }
+  parts.push(`${formatDuration(status.elapsed_secs)} berlalu`);
+  if (status.eta_secs != null) {
+    parts.push(`sisa ~${formatDuration(status.eta_secs)}`);
+  }
+  return parts.join(" • ");
+}
+
+// Combined line for parallel platforms
+function describeOverall(overall) {
+  const parts = [
+    `Total: ${overall.shops_done} toko selesai`,
+    `${overall.products_found} produk`,
+    `${formatDuration(overall.elapsed_secs)} berlalu`,
+  ];
+  if (overall.eta_secs != null) {
+    parts.push(`sisa ~${formatDuration(overall.eta_secs)}`);
+  }
+  return parts.join(" • ");
+}
+
+// Same rule as parse_quantity_prefix: "100 x 200 mm plat" is a size, not a quantity
+const SIZE_PREFIX = /^(\d|(mm|cm|m|meter|inch|in|kg|g|gr|ml|l|liter)\b)/i;
+
+function parseQuantityPrefix(text) {
+  const m = text.trim().match(/^(\d+)\s*[xX]\s+(.+)$/);
+  if (m && parseInt(m[1]) > 0 && !SIZE_PREFIX.test(m[2].trim()))
+    return { quantity: parseInt(m[1]), text: m[2].trim() };
+  return { quantity: 1, text: text.trim() };
+}
+
+// History entries saved before filters existed store queries as plain strings
+function normalizeQuery(q) {
+  if (typeof q === "string")
+    return { ...parseQuantityPrefix(q), filter: { ...EMPTY_FILTER } };
+  return {
+    ...q,
+    quantity: q.quantity || 1,
+    filter: { ...EMPTY_FILTER, ...(q.filter || {}) },
+  };
+}
+
+// "Kabel  NYM" and "kabel nym" are the same search
+function queryKey(text) {
+  return text.trim().toLowerCase().replace(/\s+/g, " ");
+}
+
+function hasFilter(filter) {
+  return (
+    !!filter &&
+    (filter.min_price != null ||
+      filter.max_price != null ||
+      filter.include_words.length > 0 ||
+      filter.exclude_words.length > 0 ||
+      filter.min_rating != null ||
+      filter.condition != null)
+  );
+}
+
 function App() {
   const [tags, setTags] = useState([]);
   const [input, setInput] = useState("");
   const [results, setResults] = useState([]);
   const [loading, setLoading] = useState(false);
+  const [scrapeStatus, setScrapeStatus] = useState({}); // Latest "status" event per platform
+  const [overallStatus, setOverallStatus] = useState(null); // All platforms combined
+  const [platformReports, setPlatformReports] = useState([]); // Per-platform outcome of the last run
+  const [runWarnings, setRunWarnings] = useState([]); // Warnings the scrapers reported during the run
   const [showDriverModal, setShowDriverModal] = useState(false);
   const [chromeInfo, setChromeInfo] = useState({
     chromeVersion: "",
@@ -18,12 +129,30 @@ function App() {
   const [expandedQueries, setExpandedQueries] = useState({}); // Track expanded queries
   const [selectedPlatform, setSelectedPlatform] = useState("tokopedia"); // Default to tokopedia
   const [searchLimit, setSearchLimit] = useState(20); // Default limit
+  const [deepScrape, setDeepScrape] = useState(false); // Visit every product page
+  const [maxSessions, setMaxSessions] = useState(2); // Parallel browser sessions in "all" mode
+  const [maxTabs, setMaxTabs] = useState(3); // Shops searched at once per session (Tokopedia)
+  const [politenessMs, setPolitenessMs] = useState(500); // Minimum delay between page loads
+  const [waitTimeout, setWaitTimeout] = useState(10); // Longest wait for a page, in seconds
+  const [tokopediaFetch, setTokopediaFetch] = useState("browser"); // "browser" or "http" (no browser)
+  const [cacheHours, setCacheHours] = useState(6); // How long shop results are reused, 0 = off
+  const [refreshCache, setRefreshCache] = useState(false); // Search every shop again
+  const [seedMode, setSeedMode] = useState("first"); // "first", "rarest", "query" or "union"
+  const [seedKeys, setSeedKeys] = useState([]); // queryKey of the picked seed queries
   const [showCopyNotification, setShowCopyNotification] = useState(false); // Track copy notification
   const [chromeProfilePath, setChromeProfilePath] = useState(""); // Chrome profile path
   const [profileSaving, setProfileSaving] = useState(false); // Track save state
+  const [diagnosing, setDiagnosing] = useState(null); // Platform whose selectors are being checked
+  const [diagnosis, setDiagnosis] = useState(null); // Last selector check report
   const [showHistoryModal, setShowHistoryModal] = useState(false); // History modal state
   const [searchHistory, setSearchHistory] = useState([]); // Search history data
+  const [editingFilterIdx, setEditingFilterIdx] = useState(null); // Tag whose filter is being edited
+  const [showShippingModal, setShowShippingModal] = useState(false); // Ongkir settings modal
+  const [shippingConfig, setShippingConfig] = useState(null); // Loaded from backend
+  const [importResult, setImportResult] = useState(null); // Last shopping list import
   const inputRef = useRef(null);
+  const importFileRef = useRef(null);
+  const importedFileRef = useRef(null); // { name, content } for re-import with another mapping
   const listenersRef = useRef([]);
   const HISTORY_STORAGE_KEY = "satu-toko-search-history";
 
@@ -42,6 +171,7 @@ function App() {
   useEffect(() => {
     let unlistenProgress = null;
     let unlistenDone = null;
+    let unlistenEvent = null;
 
     (async () => {
       try {
@@ -61,8 +191,21 @@ function App() {
 
         unlistenDone = await listen("scrape:done", () => {
           setLoading(false);
+          setScrapeStatus({});
+          setOverallStatus(null);
         });
-        listenersRef.current.push(unlistenProgress, unlistenDone);
+
+        unlistenEvent = await listen("scrape:event", (event) => {
+          const payload = event.payload;
+          if (payload.type === "status") {
+            setScrapeStatus((prev) => ({ ...prev, [payload.platform]: payload }));
+          } else if (payload.type === "overall") {
+            setOverallStatus(payload);
+          } else if (payload.type === "warning") {
+            setRunWarnings((prev) => [...prev, payload]);
+          }
+        });
+        listenersRef.current.push(unlistenProgress, unlistenDone, unlistenEvent);
       } catch (e) {
         console.error("Failed to subscribe to scrape events", e);
       }
@@ -77,7 +220,8 @@ function App() {
   function addTagFromInput() {
     const v = input.trim();
     if (!v) return;
-    setTags((t) => Array.from(new Set([...t, v])));
+    const q = normalizeQuery(v);
+    setTags((t) => (t.some((x) => x.text === q.text) ? t : [...t, q]));
     setInput("");
     inputRef.current && inputRef.current.focus();
   }
@@ -86,6 +230,27 @@ function App() {
     setTags((t) => t.filter((_, i) => i !== idx));
   }
 
+  function updateTagQuantity(idx, value) {
+    setTags((t) =>
+      t.map((q, i) => (i === idx ? { ...q, quantity: Math.max(1, value) } : q)),
+    );
+  }
+
+  function updateTagFilter(idx, field, value) {
+    setTags((t) =>
+      t.map((q, i) =>
+        i === idx ? { ...q, filter: { ...q.filter, [field]: value } } : q,
+      ),
+    );
+  }
+
+  const parseNumber = (value) => (value === "" ? null : Number(value));
+  const parseWords = (value) =>
+    value
+      .split(",")
+      .map((w) => w.trim())
+      .filter((w) => w.length > 0);
+
   // Save search history to localStorage
   const saveSearchHistory = (queries, platform, results) => {
     try {
@@ -120,7 +285,7 @@ function App() {
 
   // Load a history entry
   const loadHistoryEntry = (entry) => {
-    setTags(entry.queries);
+    setTags(entry.queries.map(normalizeQuery));
     setSelectedPlatform(entry.platform);
     setResults(entry.results || []); // Load full results
     setExpandedShops({}); // Reset expanded state
@@ -149,9 +314,33 @@ function App() {
     }
   };
 
+  // Seed queries as the backend takes them, by position in the list. Picks whose
+  // query has since been removed are dropped; none left falls back to the first.
+  function seedOption() {
+    const indices = tags
+      .map((t, i) => (seedKeys.includes(queryKey(t.text)) ? i : -1))
+      .filter((i) => i >= 0);
+    if (seedMode === "rarest") return { mode: "rarest" };
+    if (seedMode === "query" && indices.length > 0)
+      return { mode: "query", index: indices[0] };
+    if (seedMode === "union" && indices.length > 0)
+      return { mode: "union", indices };
+    return { mode: "first" };
+  }
+
+  function toggleSeedKey(key, checked) {
+    setSeedKeys((keys) =>
+      checked ? [...keys, key] : keys.filter((k) => k !== key),
+    );
+  }
+
   async function onSearch() {
     if (tags.length === 0) return;
     setResults([]);
+    setScrapeStatus({});
+    setOverallStatus(null);
+    setPlatformReports([]);
+    setRunWarnings([]);
     setLoading(true);
     try {
       // invoke backend
@@ -159,10 +348,22 @@ function App() {
         queries: tags,
         platform: selectedPlatform,
         limit: parseInt(searchLimit) || 20,
+        options: {
+          deep_scrape: deepScrape,
+          max_sessions: maxSessions,
+          max_tabs: maxTabs,
+          politeness_ms: politenessMs,
+          wait_timeout_secs: waitTimeout,
+          fetch_modes: { tokopedia: tokopediaFetch },
+          cache_ttl_secs: cacheHours * 60 * 60,
+          refresh_cache: refreshCache,
+          seed: seedOption(),
+        },
       });
-      setResults(res);
+      setResults(res.results);
+      setPlatformReports(res.platforms);
       // Save to history after successful search (with full results data)
-      saveSearchHistory(tags, selectedPlatform, res);
+      saveSearchHistory(tags, selectedPlatform, res.results);
       setLoading(false);
     } catch (e) {
       console.error(e);
@@ -171,6 +372,47 @@ function App() {
     }
   }
 
+  // Queries of the list the shown shops haven't been searched for yet
+  const unsearchedTags = tags.filter(
+    (t) =>
+      !results.some((shop) =>
+        (shop.results || []).some((r) => queryKey(r.query) === queryKey(t.text)),
+      ),
+  );
+
+  // Searches the unsearched queries in the shown shops only, without a new shop search
+  async function onExtend() {
+    if (results.length === 0 || unsearchedTags.length === 0) return;
+    setScrapeStatus({});
+    setRunWarnings([]);
+    setOverallStatus(null);
+    setLoading(true);
+    try {
+      const res = await invoke("extend_scrape", {
+        results,
+        queries: unsearchedTags,
+        limit: parseInt(searchLimit) || 20,
+        options: {
+          deep_scrape: deepScrape,
+          max_sessions: maxSessions,
+          max_tabs: maxTabs,
+          politeness_ms: politenessMs,
+          wait_timeout_secs: waitTimeout,
+          cache_ttl_secs: cacheHours * 60 * 60,
+          refresh_cache: refreshCache,
+        },
+      });
+      setResults(res.results);
+      setPlatformReports(res.platforms);
+      saveSearchHistory(tags, selectedPlatform, res.results);
+      setLoading(false);
+    } catch (e) {
+      console.error(e);
+      alert("Error while adding queries: " + String(e));
+      setLoading(false);
+    }
+  }
+
   async function loadChromeInfo() {
     setInfoLoading(true);
     try {
@@ -229,6 +471,19 @@ function App() {
     }
   }
 
+  async function onDiagnose(platform) {
+    try {
+      setDiagnosing(platform);
+      setDiagnosis(null);
+      setDiagnosis(await invoke("diagnose_platform", { platform }));
+    } catch (e) {
+      console.error(e);
+      alert("Gagal menjalankan cek selector: " + String(e));
+    } finally {
+      setDiagnosing(null);
+    }
+  }
+
   async function onOpenShopee() {
     try {
       await invoke("open_chrome_with_driver", {
@@ -253,6 +508,65 @@ function App() {
     }
   }
 
+  async function onOpenShipping() {
+    try {
+      setShippingConfig(await invoke("get_shipping_config"));
+      setShowShippingModal(true);
+    } catch (e) {
+      console.error(e);
+      alert("Gagal memuat pengaturan ongkir: " + String(e));
+    }
+  }
+
+  async function saveShippingConfig() {
+    try {
+      await invoke("set_shipping_config", { config: shippingConfig });
+      setShowShippingModal(false);
+    } catch (e) {
+      console.error(e);
+      alert("Gagal menyimpan pengaturan ongkir: " + String(e));
+    }
+  }
+
+  const updateShipping = (field, value) =>
+    setShippingConfig((c) => ({ ...c, [field]: value }));
+
+  async function runImport(mapping) {
+    const file = importedFileRef.current;
+    if (!file) return;
+    try {
+      const res = await invoke("import_shopping_list", {
+        fileName: file.name,
+        content: file.content,
+        mapping,
+      });
+      setImportResult(res);
+    } catch (e) {
+      console.error(e);
+      alert("Gagal import daftar belanja: " + String(e));
+    }
+  }
+
+  async function onImportFile(e) {
+    const file = e.target.files && e.target.files[0];
+    e.target.value = ""; // allow picking the same file again
+    if (!file) return;
+    const buffer = await file.arrayBuffer();
+    importedFileRef.current = {
+      name: file.name,
+      content: Array.from(new Uint8Array(buffer)),
+    };
+    runImport(null);
+  }
+
+  function applyImport() {
+    setTags(importResult.queries.map(normalizeQuery));
+    setImportResult(null);
+  }
+
+  const updateImportMapping = (field, value) =>
+    runImport({ ...importResult.mapping, [field]: value || null });
+
   function closeModal() {
     setShowDriverModal(false);
   }
@@ -351,6 +665,9 @@ function App() {
               >
                 Open Tokopedia
               </button>
+              <button onClick={onOpenShipping} className="btn-secondary">
+                Ongkir
+              </button>
               <button onClick={onOpenDriver} className="btn-secondary">
                 Chromedriver Setting
               </button>
@@ -463,7 +780,9 @@ function App() {
                                 marginRight: "4px",
                               }}
                             >
-                              {q}
+                              {typeof q === "string"
+                                ? q
+                                : `${q.quantity > 1 ? `${q.quantity}x ` : ""}${q.text}`}
                             </span>
                           ))}
                         </div>
@@ -649,6 +968,74 @@ function App() {
                   </div>
                 </div>
 
+                <div className="info-grid" style={{ marginTop: "20px" }}>
+                  <div className="info-item">
+                    <label className="info-label">Cek Selector</label>
+                    <div style={{ display: "flex", gap: "8px" }}>
+                      {["tokopedia", "shopee"].map((p) => (
+                        <button
+                          key={p}
+                          onClick={() => onDiagnose(p)}
+                          disabled={diagnosing !== null}
+                          className="btn-secondary"
+                          style={{ flex: 1 }}
+                        >
+                          {diagnosing === p ? "Memeriksa..." : `Cek ${p}`}
+                        </button>
+                      ))}
+                    </div>
+                    {diagnosis && (
+                      <div style={{ marginTop: "8px", fontSize: "13px" }}>
+                        <div>
+                          <strong>
+                            {diagnosis.platform}:{" "}
+                            {diagnosis.healthy ? "semua selector cocok" : "ada selector yang gagal"}
+                          </strong>{" "}
+                          (versi {diagnosis.selectors_version}
+                          {diagnosis.selectors_override ? ", override" : ""},{" "}
+                          {diagnosis.cards_sampled} kartu)
+                        </div>
+                        {diagnosis.errors.map((error, i) => (
+                          <div key={i} className="diagnosis-failed">
+                            {error}
+                          </div>
+                        ))}
+                        <table className="diagnosis-table">
+                          <tbody>
+                            {diagnosis.checks.map((check) => (
+                              <tr
+                                key={check.page + check.key}
+                                className={
+                                  check.count === 0 && check.required
+                                    ? "diagnosis-failed"
+                                    : ""
+                                }
+                              >
+                                <td>{check.key}</td>
+                                <td>{check.page}</td>
+                                <td>{check.count}</td>
+                                <td>
+                                  {check.matched_rule !== null
+                                    ? `aturan ${check.matched_rule + 1}`
+                                    : check.required
+                                      ? "tidak cocok"
+                                      : "tidak ada"}
+                                </td>
+                              </tr>
+                            ))}
+                          </tbody>
+                        </table>
+                        {diagnosis.bundle && (
+                          <div>
+                            Sumber halaman dan screenshot disimpan di{" "}
+                            <code>{diagnosis.bundle}</code>
+                          </div>
+                        )}
+                      </div>
+                    )}
+                  </div>
+                </div>
+
                 <div className="modal-actions">
                   <button
                     onClick={onReDownload}
@@ -664,15 +1051,379 @@ function App() {
           </div>
         )}
 
+        {/* Shopping List Import Modal */}
+        {importResult && (
+          <div className="modal-overlay">
+            <div className="modal-window">
+              <div className="modal-header">
+                <h3 className="modal-title">Import Daftar Belanja</h3>
+                <button
+                  onClick={() => setImportResult(null)}
+                  className="btn-close"
+                >
+                  ×
+                </button>
+              </div>
+
+              <div className="modal-body">
+                <div className="form-row">
+                  {[
+                    ["name", "Nama Item"],
+                    ["quantity", "Jumlah"],
+                    ["max_price", "Harga Max"],
+                    ["notes", "Catatan"],
+                  ].map(([field, label]) => (
+                    <div className="form-group" key={field}>
+                      <label className="form-label">{label}</label>
+                      <select
+                        value={importResult.mapping[field] || ""}
+                        onChange={(e) =>
+                          updateImportMapping(field, e.target.value)
+                        }
+                        className="form-select"
+                      >
+                        {field !== "name" && <option value="">-</option>}
+                        {importResult.headers.map((h) => (
+                          <option key={h} value={h}>
+                            {h}
+                          </option>
+                        ))}
+                      </select>
+                    </div>
+                  ))}
+                </div>
+
+                <p className="text-sm">
+                  {importResult.queries.length} item siap diimport
+                </p>
+                {importResult.errors.length > 0 && (
+                  <div className="warning-box" style={{ fontSize: "13px" }}>
+                    <div className="warning-content">
+                      <strong>
+                        {importResult.errors.length} baris dilewati:
+                      </strong>
+                      <ul style={{ paddingLeft: "20px", marginBottom: 0 }}>
+                        {importResult.errors.map((err) => (
+                          <li key={err.row}>
+                            Baris {err.row}: {err.message}
+                          </li>
+                        ))}
+                      </ul>
+                    </div>
+                  </div>
+                )}
+
+                <div className="modal-actions">
+                  <button
+                    onClick={applyImport}
+                    disabled={importResult.queries.length === 0}
+                    className="btn-primary"
+                    style={{ width: "100%" }}
+                  >
+                    Gunakan Daftar
+                  </button>
+                </div>
+              </div>
+            </div>
+          </div>
+        )}
+
+        {/* Shipping Settings Modal */}
+        {showShippingModal && shippingConfig && (
+          <div className="modal-overlay">
+            <div className="modal-window">
+              <div className="modal-header">
+                <h3 className="modal-title">Pengaturan Ongkir</h3>
+                <button
+                  onClick={() => setShowShippingModal(false)}
+                  className="btn-close"
+                >
+                  ×
+                </button>
+              </div>
+
+              <div className="modal-body">
+                <div className="form-group">
+                  <label className="form-label">
+                    <input
+                      type="checkbox"
+                      checked={shippingConfig.enabled}
+                      onChange={(e) => updateShipping("enabled", e.target.checked)}
+                    />{" "}
+                    Hitung estimasi ongkir
+                  </label>
+                </div>
+                <div className="form-row">
+                  <div className="form-group">
+                    <label className="form-label">Kota Tujuan</label>
+                    <input
+                      type="text"
+                      value={shippingConfig.destination_city}
+                      onChange={(e) =>
+                        updateShipping("destination_city", e.target.value)
+                      }
+                      className="form-select"
+                    />
+                  </div>
+                  <div className="form-group">
+                    <label className="form-label">Kecamatan</label>
+                    <input
+                      type="text"
+                      value={shippingConfig.destination_district}
+                      onChange={(e) =>
+                        updateShipping("destination_district", e.target.value)
+                      }
+                      className="form-select"
+                    />
+                  </div>
+                </div>
+                <div className="form-group">
+                  <label className="form-label">
+                    File CSV tarif kurir (kosongkan untuk tarif perkiraan)
+                  </label>
+                  <input
+                    type="text"
+                    value={shippingConfig.rates_csv}
+                    onChange={(e) => updateShipping("rates_csv", e.target.value)}
+                    placeholder="courier,origin,destination,price_per_kg,etd"
+                    className="form-select"
+                    style={{ width: "100%" }}
+                  />
+                </div>
+                <div className="form-row">
+                  <div className="form-group">
+                    <label className="form-label">Berat default (gram)</label>
+                    <input
+                      type="number"
+                      min="1"
+                      value={shippingConfig.default_weight_grams}
+                      onChange={(e) =>
+                        updateShipping(
+                          "default_weight_grams",
+                          parseInt(e.target.value) || 1000,
+                        )
+                      }
+                      className="form-select"
+                    />
+                  </div>
+                  <div className="form-group">
+                    <label className="form-label">
+                      <input
+                        type="checkbox"
+                        checked={shippingConfig.fetch_weights}
+                        onChange={(e) =>
+                          updateShipping("fetch_weights", e.target.checked)
+                        }
+                      />{" "}
+                      Ambil berat dari halaman produk
+                    </label>
+                  </div>
+                </div>
+
+                <div className="modal-actions">
+                  <button
+                    onClick={saveShippingConfig}
+                    className="btn-primary"
+                    style={{ width: "100%" }}
+                  >
+                    Simpan
+                  </button>
+                </div>
+              </div>
+            </div>
+          </div>
+        )}
+
+        {/* Query Filter Modal */}
+        {editingFilterIdx !== null && tags[editingFilterIdx] && (
+          <div className="modal-overlay">
+            <div className="modal-window" key={editingFilterIdx}>
+              <div className="modal-header">
+                <h3 className="modal-title">
+                  Filter: {tags[editingFilterIdx].text}
+                </h3>
+                <button
+                  onClick={() => setEditingFilterIdx(null)}
+                  className="btn-close"
+                >
+                  ×
+                </button>
+              </div>
+
+              <div className="modal-body">
+                <div className="form-group">
+                  <label className="form-label">Jumlah</label>
+                  <input
+                    type="number"
+                    min="1"
+                    value={tags[editingFilterIdx].quantity}
+                    onChange={(e) =>
+                      updateTagQuantity(
+                        editingFilterIdx,
+                        parseInt(e.target.value) || 1,
+                      )
+                    }
+                    className="form-select"
+                  />
+                </div>
+                <div className="form-row">
+                  <div className="form-group">
+                    <label className="form-label">Harga Min</label>
+                    <input
+                      type="number"
+                      min="0"
+                      value={tags[editingFilterIdx].filter.min_price ?? ""}
+                      onChange={(e) =>
+                        updateTagFilter(
+                          editingFilterIdx,
+                          "min_price",
+                          parseNumber(e.target.value),
+                        )
+                      }
+                      className="form-select"
+                    />
+                  </div>
+                  <div className="form-group">
+                    <label className="form-label">Harga Max</label>
+                    <input
+                      type="number"
+                      min="0"
+                      value={tags[editingFilterIdx].filter.max_price ?? ""}
+                      onChange={(e) =>
+                        updateTagFilter(
+                          editingFilterIdx,
+                          "max_price",
+                          parseNumber(e.target.value),
+                        )
+                      }
+                      className="form-select"
+                    />
+                  </div>
+                </div>
+                <div className="form-group">
+                  <label className="form-label">
+                    Harus mengandung kata (pisahkan dengan koma)
+                  </label>
+                  <input
+                    type="text"
+                    defaultValue={tags[editingFilterIdx].filter.include_words.join(
+                      ", ",
+                    )}
+                    onBlur={(e) =>
+                      updateTagFilter(
+                        editingFilterIdx,
+                        "include_words",
+                        parseWords(e.target.value),
+                      )
+                    }
+                    className="form-select"
+                    style={{ width: "100%" }}
+                  />
+                </div>
+                <div className="form-group">
+                  <label className="form-label">
+                    Kecualikan kata (pisahkan dengan koma)
+                  </label>
+                  <input
+                    type="text"
+                    defaultValue={tags[editingFilterIdx].filter.exclude_words.join(
+                      ", ",
+                    )}
+                    onBlur={(e) =>
+                      updateTagFilter(
+                        editingFilterIdx,
+                        "exclude_words",
+                        parseWords(e.target.value),
+                      )
+                    }
+                    className="form-select"
+                    style={{ width: "100%" }}
+                  />
+                </div>
+                <div className="form-row">
+                  <div className="form-group">
+                    <label className="form-label">Rating Toko Min</label>
+                    <select
+                      value={tags[editingFilterIdx].filter.min_rating ?? ""}
+                      onChange={(e) =>
+                        updateTagFilter(
+                          editingFilterIdx,
+                          "min_rating",
+                          parseNumber(e.target.value),
+                        )
+                      }
+                      className="form-select"
+                    >
+                      <option value="">Semua</option>
+                      <option value="4">4 ke atas</option>
+                      <option value="4.5">4.5 ke atas</option>
+                    </select>
+                  </div>
+                  <div className="form-group">
+                    <label className="form-label">Kondisi</label>
+                    <select
+                      value={tags[editingFilterIdx].filter.condition ?? ""}
+                      onChange={(e) =>
+                        updateTagFilter(
+                          editingFilterIdx,
+                          "condition",
+                          e.target.value || null,
+                        )
+                      }
+                      className="form-select"
+                    >
+                      <option value="">Semua</option>
+                      <option value="new">Baru</option>
+                      <option value="used">Bekas</option>
+                    </select>
+                  </div>
+                </div>
+
+                <div className="modal-actions">
+                  <button
+                    onClick={() => setEditingFilterIdx(null)}
+                    className="btn-primary"
+                    style={{ width: "100%" }}
+                  >
+                    Simpan
+                  </button>
+                </div>
+              </div>
+            </div>
+          </div>
+        )}
+
         <div className="content-section">
           <div className="form-group">
             <label className="form-label">
-              Nama produk (bisa lebih dari satu)
+              Nama produk (bisa lebih dari satu){" "}
+              <button
+                onClick={() => importFileRef.current && importFileRef.current.click()}
+                className="btn-secondary"
+                style={{ fontSize: "12px", padding: "2px 8px" }}
+              >
+                Import CSV/XLSX
+              </button>
+              <input
+                ref={importFileRef}
+                type="file"
+                accept=".csv,.xlsx,.xls,.ods,.txt"
+                style={{ display: "none" }}
+                onChange={onImportFile}
+              />
             </label>
             <div className="tag-input">
               {tags.map((t, i) => (
                 <span key={i} className="tag">
-                  {t}
+                  {t.quantity > 1 && `${t.quantity}x `}
+                  {t.text}
+                  <button
+                    onClick={() => setEditingFilterIdx(i)}
+                    className="tag-remove"
+                    title="Filter"
+                  >
+                    {hasFilter(t.filter) ? "⚙*" : "⚙"}
+                  </button>
                   <button onClick={() => removeTag(i)} className="tag-remove">
                     ×
                   </button>
@@ -694,7 +1445,7 @@ function App() {
                     removeTag(tags.length - 1);
                   }
                 }}
-                placeholder="Ketik nama produk lalu tekan Enter atau koma"
+                placeholder="Ketik nama produk (mis. 10x kabel NYM) lalu tekan Enter atau koma"
                 className="tag-input-field"
               />
             </div>
@@ -710,8 +1461,103 @@ function App() {
               >
                 <option value="tokopedia">Tokopedia</option>
                 <option value="shopee">Shopee</option>
+                <option value="all">Semua (Tokopedia + Shopee)</option>
               </select>
             </div>
+            {selectedPlatform === "all" && (
+              <div className="form-group">
+                <label
+                  className="form-label"
+                  title="Jumlah browser yang berjalan bersamaan (1 = bergantian)"
+                >
+                  Sesi Paralel
+                </label>
+                <input
+                  type="number"
+                  value={maxSessions}
+                  onChange={(e) =>
+                    setMaxSessions(Math.max(1, parseInt(e.target.value) || 1))
+                  }
+                  className="form-select"
+                  min="1"
+                  max="2"
+                />
+              </div>
+            )}
+            {selectedPlatform !== "shopee" && (
+              <div className="form-group">
+                <label
+                  className="form-label"
+                  title="Jumlah toko Tokopedia yang dicari bersamaan dalam satu browser (tab)"
+                >
+                  Tab Paralel
+                </label>
+                <input
+                  type="number"
+                  value={maxTabs}
+                  onChange={(e) =>
+                    setMaxTabs(Math.max(1, parseInt(e.target.value) || 1))
+                  }
+                  className="form-select"
+                  min="1"
+                  max="6"
+                />
+              </div>
+            )}
+            {selectedPlatform !== "shopee" && (
+              <div className="form-group">
+                <label
+                  className="form-label"
+                  title="HTTP mengunduh halaman pencarian dan toko Tokopedia tanpa membuka browser (lebih ringan). Browser tetap dipakai bila Tokopedia memblokir, dan untuk halaman produk"
+                >
+                  Mode Tokopedia
+                </label>
+                <select
+                  value={tokopediaFetch}
+                  onChange={(e) => setTokopediaFetch(e.target.value)}
+                  className="form-select"
+                >
+                  <option value="browser">Browser</option>
+                  <option value="http">HTTP (tanpa browser)</option>
+                </select>
+              </div>
+            )}
+            <div className="form-group">
+              <label
+                className="form-label"
+                title="Jeda minimum antar pemuatan halaman toko Tokopedia (milidetik), agar tidak terlalu membebani situs"
+              >
+                Jeda (ms)
+              </label>
+              <input
+                type="number"
+                value={politenessMs}
+                onChange={(e) =>
+                  setPolitenessMs(Math.max(0, parseInt(e.target.value) || 0))
+                }
+                className="form-select"
+                min="0"
+                step="100"
+              />
+            </div>
+            <div className="form-group">
+              <label
+                className="form-label"
+                title="Batas waktu menunggu halaman atau produk muncul (detik). Naikkan untuk koneksi lambat"
+              >
+                Batas Tunggu (detik)
+              </label>
+              <input
+                type="number"
+                value={waitTimeout}
+                onChange={(e) =>
+                  setWaitTimeout(Math.max(1, parseInt(e.target.value) || 1))
+                }
+                className="form-select"
+                min="1"
+                max="60"
+              />
+            </div>
             <div className="form-group">
               <label className="form-label">Max Items</label>
               <input
@@ -723,6 +1569,105 @@ function App() {
                 max="500"
               />
             </div>
+            <div className="form-group">
+              <label
+                className="form-label"
+                title="Kueri yang hasil pencariannya dipakai untuk menemukan toko; kueri lain hanya dicari di dalam toko tersebut"
+              >
+                Kueri Pemandu
+              </label>
+              <select
+                value={seedMode}
+                onChange={(e) => setSeedMode(e.target.value)}
+                className="form-select"
+              >
+                <option value="first">Pertama</option>
+                <option
+                  value="rarest"
+                  title="Membuka halaman hasil pertama setiap kueri untuk menghitung hasilnya. Bila jumlahnya tidak tertulis, hanya kartu di halaman pertama yang terhitung, sehingga kueri yang sama-sama banyak tidak bisa dibedakan"
+                >
+                  Otomatis (paling jarang)
+                </option>
+                <option value="query">Pilih kueri</option>
+                <option value="union">Gabungan</option>
+              </select>
+            </div>
+            {seedMode === "query" && tags.length > 0 && (
+              <div className="form-group">
+                <label className="form-label">Kueri</label>
+                <select
+                  value={
+                    seedKeys.find((k) => tags.some((t) => queryKey(t.text) === k)) ??
+                    queryKey(tags[0].text)
+                  }
+                  onChange={(e) => setSeedKeys([e.target.value])}
+                  className="form-select"
+                >
+                  {tags.map((t, i) => (
+                    <option key={i} value={queryKey(t.text)}>
+                      {t.text}
+                    </option>
+                  ))}
+                </select>
+              </div>
+            )}
+            {seedMode === "union" && tags.length > 0 && (
+              <div
+                className="form-group"
+                title="Toko dikumpulkan dari hasil setiap kueri yang dicentang"
+              >
+                {tags.map((t, i) => (
+                  <label key={i} className="form-label">
+                    <input
+                      type="checkbox"
+                      checked={seedKeys.includes(queryKey(t.text))}
+                      onChange={(e) =>
+                        toggleSeedKey(queryKey(t.text), e.target.checked)
+                      }
+                    />{" "}
+                    {t.text}
+                  </label>
+                ))}
+              </div>
+            )}
+            <div className="form-group">
+              <label className="form-label" title="Buka halaman setiap produk untuk stok, varian, berat dan deskripsi (lebih lama)">
+                <input
+                  type="checkbox"
+                  checked={deepScrape}
+                  onChange={(e) => setDeepScrape(e.target.checked)}
+                />{" "}
+                Detail produk
+              </label>
+            </div>
+            <div className="form-group">
+              <label
+                className="form-label"
+                title="Hasil pencarian di toko yang sama dipakai ulang selama sekian jam, jadi mengubah daftar belanja hanya mencari kueri baru. 0 mematikan cache"
+              >
+                Cache (jam)
+              </label>
+              <input
+                type="number"
+                value={cacheHours}
+                onChange={(e) =>
+                  setCacheHours(Math.max(0, parseInt(e.target.value) || 0))
+                }
+                className="form-select"
+                min="0"
+                max="168"
+              />
+            </div>
+            <div className="form-group">
+              <label className="form-label" title="Abaikan hasil yang tersimpan dan cari ulang di setiap toko">
+                <input
+                  type="checkbox"
+                  checked={refreshCache}
+                  onChange={(e) => setRefreshCache(e.target.checked)}
+                />{" "}
+                Segarkan cache
+              </label>
+            </div>
             <div className="form-group">
               <button
                 onClick={onSearch}
@@ -732,10 +1677,22 @@ function App() {
                 Cari Produk
               </button>
             </div>
+            {results.length > 0 && (
+              <div className="form-group">
+                <button
+                  onClick={onExtend}
+                  disabled={loading || unsearchedTags.length === 0}
+                  className="btn-secondary"
+                  title="Cari kueri baru hanya di toko yang sudah ditemukan, tanpa mengulang pencarian toko"
+                >
+                  Tambah ke Hasil ({unsearchedTags.length})
+                </button>
+              </div>
+            )}
           </div>
 
           {/* Shopee Warning */}
-          {selectedPlatform === "shopee" && (
+          {(selectedPlatform === "shopee" || selectedPlatform === "all") && (
             <div className="warning-box">
               <div className="warning-icon">⚠</div>
               <div className="warning-content">
@@ -775,9 +1732,47 @@ function App() {
 
           <div>
             {loading && (
-              <p className="text-sm text-gray-500">
-                Mencari... tunggu sebentar
-              </p>
+              <div className="text-sm text-gray-500">
+                {Object.keys(scrapeStatus).length === 0 && (
+                  <p>Mencari... tunggu sebentar</p>
+                )}
+                {overallStatus && Object.keys(scrapeStatus).length > 1 && (
+                  <p>{describeOverall(overallStatus)}</p>
+                )}
+                {Object.values(scrapeStatus).map((status) => (
+                  <p key={status.platform}>{describeStatus(status)}</p>
+                ))}
+              </div>
+            )}
+            {platformReports
+              .filter((r) => r.status !== "ok")
+              .map((r) => (
+                <div
+                  key={r.platform}
+                  className="warning-box"
+                  style={{ fontSize: "13px" }}
+                >
+                  <div className="warning-content">
+                    <strong>
+                      {r.platform === "shopee" ? "Shopee" : "Tokopedia"}
+                      {r.status === "partial"
+                        ? ` gagal di tengah jalan (${r.shops} toko tetap ditampilkan): `
+                        : " gagal: "}
+                    </strong>
+                    {r.reason}
+                  </div>
+                </div>
+              ))}
+            {runWarnings.length > 0 && (
+              <div className="warning-box" style={{ fontSize: "13px" }}>
+                <div className="warning-content">
+                  {runWarnings.map((w, i) => (
+                    <p key={i}>
+                      {w.platform === "shopee" ? "Shopee" : "Tokopedia"}: {w.message}
+                    </p>
+                  ))}
+                </div>
+              </div>
             )}
             {!loading && results.length === 0 && (
               <p className="text-sm text-gray-500">Belum ada hasil</p>
@@ -788,6 +1783,10 @@ function App() {
                 shop.results &&
                 shop.results.length > 0 &&
                 shop.results.every((r) => r.products && r.products.length > 0);
+              // queries whose shop search never loaded: "not found" can't be trusted
+              const failedCount = shop.results
+                ? shop.results.filter((r) => r.status === "failed").length
+                : 0;
               const isShopExpanded = expandedShops[sIdx] || false;
 
               return (
@@ -807,11 +1806,32 @@ function App() {
                       /{shop.results ? shop.results.length : 0})
                     </h4>
                     <div className="flex items-center gap-3">
+                      {shop.basket && (
+                        <div
+                          className="text-sm text-green-700"
+                          title={
+                            shop.basket.shipping
+                              ? `Barang ${formatRupiah(shop.basket.items_total)} + ongkir ${formatRupiah(shop.basket.shipping.cost)} (${shop.basket.shipping.courier}, ${shop.basket.shipping.weight_grams} g)`
+                              : "Belum termasuk ongkir"
+                          }
+                        >
+                          {formatRupiah(shop.basket.total)}
+                          {shop.basket.shipping && " (+ongkir)"}
+                        </div>
+                      )}
                       {allFound && (
                         <div className="badge bg-green-100 text-green-800 text-sm px-2 py-1 rounded">
                           Semua produk ditemukan
                         </div>
                       )}
+                      {failedCount > 0 && (
+                        <div
+                          className="badge bg-yellow-100 text-yellow-800 text-sm px-2 py-1 rounded"
+                          title="Halaman pencarian toko gagal dimuat; barang mungkin tetap dijual di toko ini"
+                        >
+                          {failedCount} pencarian gagal
+                        </div>
+                      )}
                       <svg
                         className={`expandable-icon ${isShopExpanded ? "rotated" : ""}`}
                         width="16"
@@ -844,6 +1864,45 @@ function App() {
                         </a>
                       </div>
 
+                      {shop.basket && shop.basket.lines.length > 0 && (
+                        <div className="mb-2 text-xs">
+                          {shop.basket.lines.map((line, lIdx) => (
+                            <div key={lIdx} className="flex justify-between">
+                              <span>
+                                {line.quantity}x {line.product_name}
+                                {line.warning && (
+                                  <span className="text-red-600">
+                                    {" "}
+                                    ({line.warning})
+                                  </span>
+                                )}
+                              </span>
+                              <span>{formatRupiah(line.line_total)}</span>
+                            </div>
+                          ))}
+                        </div>
+                      )}
+
+                      {shop.profile && (
+                        <div className="mb-2 text-xs text-gray-600">
+                          {[
+                            shop.profile.rating != null &&
+                              `★ ${shop.profile.rating}${shop.profile.review_count != null ? ` (${shop.profile.review_count} ulasan)` : ""}`,
+                            shop.profile.city,
+                            shop.profile.response_time,
+                            shop.profile.joined &&
+                              `Bergabung ${shop.profile.joined}`,
+                            shop.profile.online_status,
+                            shop.profile.is_closed && "Toko tutup",
+                            ...(shop.profile.badges || []).map(
+                              (b) => BADGE_LABELS[b] || b,
+                            ),
+                          ]
+                            .filter(Boolean)
+                            .join(" • ")}
+                        </div>
+                      )}
+
                       {shop.results &&
                         shop.results.map((qr, qIdx) => {
                           const key = `${sIdx}-${qIdx}`;
@@ -859,12 +1918,29 @@ function App() {
                               >
                                 <div className="flex items-center gap-2">
                                   <span className="query-pill text-sm">
+                                    {qr.quantity > 1 && `${qr.quantity}x `}
                                     {qr.query}
                                   </span>
                                   <span className="query-count text-sm text-gray-500">
                                     ({qr.products ? qr.products.length : 0}{" "}
                                     products)
                                   </span>
+                                  {qr.status === "failed" && (
+                                    <span
+                                      className="query-failed text-sm"
+                                      title={qr.error || ""}
+                                    >
+                                      gagal dimuat
+                                    </span>
+                                  )}
+                                  {qr.cached_age_secs != null && (
+                                    <span
+                                      className="query-cached text-sm"
+                                      title="Diambil dari pencarian sebelumnya"
+                                    >
+                                      cache ({formatAge(qr.cached_age_secs)})
+                                    </span>
+                                  )}
                                 </div>
                                 <svg
                                   className={`expandable-icon ${isQueryExpanded ? "rotated" : ""}`}
@@ -884,7 +1960,9 @@ function App() {
                                 <div className="query-products">
                                   {!hasProducts ? (
                                     <p className="no-results">
-                                      Tidak ada hasil
+                                      {qr.status === "failed"
+                                        ? `Halaman gagal dimuat, hasil tidak bisa dipastikan${qr.error ? ` (${qr.error})` : ""}`
+                                        : "Tidak ada hasil"}
                                     </p>
                                   ) : (
                                     <div className="product-list">
@@ -918,6 +1996,49 @@ function App() {
                                               {p.price && (
                                                 <div className="product-price text-green-600 text-sm">
                                                   {p.price}
+                                                  {p.original_price && (
+                                                    <s className="text-gray-500 text-xs ml-2">
+                                                      {p.original_price}
+                                                    </s>
+                                                  )}
+                                                  {p.discount_percent && (
+                                                    <span className="text-red-600 text-xs ml-1">
+                                                      -{p.discount_percent}%
+                                                    </span>
+                                                  )}
+                                                </div>
+                                              )}
+                                              {(p.rating || p.sold_count) && (
+                                                <div className="text-xs text-gray-500">
+                                                  {p.rating && <>★ {p.rating}</>}
+                                                  {p.rating && p.sold_count && " • "}
+                                                  {p.sold_count && (
+                                                    <>{p.sold_count} terjual</>
+                                                  )}
+                                                </div>
+                                              )}
+                                              {p.detail && (
+                                                <div className="text-xs text-gray-500">
+                                                  {[
+                                                    p.detail.stock != null &&
+                                                      `Stok ${p.detail.stock}`,
+                                                    p.detail.min_order != null &&
+                                                      `Min. ${p.detail.min_order}`,
+                                                    p.detail.weight_grams != null &&
+                                                      `${p.detail.weight_grams} g`,
+                                                    p.detail.variants &&
+                                                      p.detail.variants.length > 0 &&
+                                                      `${p.detail.variants.length} varian`,
+                                                  ]
+                                                    .filter(Boolean)
+                                                    .join(" • ")}
+                                                </div>
+                                              )}
+                                              {p.badges && p.badges.length > 0 && (
+                                                <div className="text-xs text-blue-700">
+                                                  {p.badges
+                                                    .map((b) => BADGE_LABELS[b] || b)
+                                                    .join(", ")}
                                                 </div>
                                               )}
                                               <a